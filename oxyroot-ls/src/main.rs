@@ -12,6 +12,11 @@ struct Cli {
     /// Path to the file to list
     #[arg(short, long, value_name = "FILE")]
     file: PathBuf,
+
+    /// Check the integrity of the file instead of listing it: keys, baskets and free segments.
+    /// Exits with 2 if problems are found or the file can not be checked.
+    #[arg(long)]
+    check: bool,
 }
 
 fn main() {
@@ -39,20 +44,48 @@ fn main() {
         std::process::exit(1);
     }
 
+    if cli.check {
+        // files of crashed jobs can not be opened, only their header has to be readable
+        let report = match oxyroot::RootFile::verify_path(file) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("can not verify {:?}: {:?}", file, e);
+                std::process::exit(2);
+            }
+        };
+        println!("{}", report);
+        if !report.is_ok() {
+            std::process::exit(2);
+        }
+        return;
+    }
+
     let mut f = oxyroot::RootFile::open(file).expect("Can not open file");
 
     // trace!("keys = {:?}", keys);
 
-    let keys = f.keys();
+    let dir = f.get_directory("").expect("Can not read top directory");
+    list_dir(&dir);
+}
+
+fn list_dir(dir: &oxyroot::Directory) {
+    let keys = dir.keys();
     for k in keys {
-        println!(
-            "> {} name='{}' (title='{}')",
-            k.class(),
-            k.name(),
-            k.title()
-        );
-        println!("> Data in {}:", k.name());
-        let tree = f.get_tree(k.name()).unwrap();
+        let path = if dir.path().is_empty() {
+            k.name().to_string()
+        } else {
+            format!("{}/{}", dir.path(), k.name())
+        };
+        println!("> {} name='{}' (title='{}')", k.class(), path, k.title());
+        if k.class() == "TDirectoryFile" {
+            let sub = dir.get_directory(k.name()).unwrap();
+            list_dir(&sub);
+            continue;
+        }
+        println!("> Data in {};{}:", path, k.cycle());
+        let tree = dir
+            .get_tree(&format!("{};{}", k.name(), k.cycle()))
+            .unwrap();
         tree.show();
     }
 }
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use std::process::Command;
+
+const HZZ: &str = "../oxyroot/examples/from_uproot/data/HZZ.root";
+
+#[test]
+fn check_sound_file() -> Result<()> {
+    let out = Command::new(env!("CARGO_BIN_EXE_oxyroot-ls"))
+        .args(["--file", HZZ, "--check"])
+        .output()?;
+    let stdout = String::from_utf8(out.stdout)?;
+    assert_eq!(out.status.code(), Some(0), "{stdout}");
+    assert!(stdout.contains("0 problem(s)"), "{stdout}");
+    Ok(())
+}
+
+#[test]
+fn check_truncated_file() -> Result<()> {
+    let out_dir = "/tmp/rust/oxyroot-ls";
+    std::fs::create_dir_all(out_dir)?;
+    let out_file = format!("{out_dir}/HZZ-truncated.root");
+    let data = std::fs::read(HZZ)?;
+    std::fs::write(&out_file, &data[..data.len() / 2])?;
+
+    let out = Command::new(env!("CARGO_BIN_EXE_oxyroot-ls"))
+        .args(["--file", &out_file, "--check"])
+        .output()?;
+    let stdout = String::from_utf8(out.stdout)?;
+    assert_eq!(out.status.code(), Some(2), "{stdout}");
+    assert!(stdout.contains("file is truncated"), "{stdout}");
+    Ok(())
+}
@@ -4,6 +4,9 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     TypeMismatch { expected: String, given: String },
     BranchNotFound { name: String },
+    EntryOutOfRange { entry: i64, entries: i64 },
+    // a basket can not be read or decompressed
+    Riofs(crate::riofs::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -13,3 +16,9 @@ impl std::fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+impl From<crate::riofs::Error> for Error {
+    fn from(e: crate::riofs::Error) -> Self {
+        Error::Riofs(e)
+    }
+}
@@ -200,9 +200,16 @@ mod rusty;
 mod rvers;
 mod utils;
 
-pub use riofs::file::RootFile;
+pub use rcompress::{CompressionAlgorithm, CompressionSettings};
+pub use riofs::dir::{Directory, DirectoryWriter, WriteDestination};
+pub use riofs::file::{CreateOptions, OpenOptions, RootFile};
+pub use riofs::ReaderAt;
+pub use riofs::{Problem, VerifyReport};
 pub use rtree::branch::Branch;
 pub use rtree::tree::reader::ReaderTree;
+pub use rtree::tree::ClusterChunk;
+pub use rtree::tree::FriendElement;
+pub use rtree::tree::TreeIndex;
 
 pub use rtree::tree::traits::ReadFromTree;
 pub use rtree::tree::traits::ReadFromTreeResult;
@@ -223,6 +230,8 @@ pub use rusty::Slice;
 pub use root::traits::Named;
 pub use root::traits::Object;
 
+pub use rtypes::factory::{FactoryItemRead, FactoryItemWrite};
+
 pub use error::Result;
 
 /// Derive macro available if oxyroot is built with `features = ["derive"]`.
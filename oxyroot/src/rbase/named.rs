@@ -2,7 +2,7 @@ use crate::rbytes::rbuffer::RBuffer;
 use crate::rbytes::wbuffer::WBuffer;
 use crate::rbytes::{RVersioner, Unmarshaler};
 use crate::root::traits::Object;
-use crate::{rbase, Marshaler};
+use crate::{factory_fn_register_impl, rbase, Marshaler};
 use crate::{root, rvers};
 use log::trace;
 
@@ -18,11 +18,7 @@ pub struct Named {
     pub(crate) title: String,
 }
 
-impl root::traits::Object for Named {
-    fn class(&self) -> &'_ str {
-        "TNamed"
-    }
-}
+factory_fn_register_impl!(Named, "TNamed");
 
 impl root::traits::Named for Named {
     fn name(&self) -> &'_ str {
@@ -35,6 +31,14 @@ impl root::traits::Named for Named {
 }
 
 impl Named {
+    pub fn new<S: Into<String>, T: Into<String>>(name: S, title: T) -> Self {
+        Self {
+            name: name.into(),
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
     pub(crate) fn with_name(mut self, name: String) -> Self {
         self.name = name;
         self
@@ -69,7 +73,7 @@ impl Unmarshaler for Named {
 
 impl Marshaler for Named {
     fn marshal(&self, w: &mut WBuffer) -> crate::rbytes::Result<i64> {
-        let len = w.len() - 1;
+        let len = w.len().saturating_sub(1);
         let beg = w.pos();
         trace!(";Named.marshal.a{beg}.beg:{}", beg);
         trace!(";Named.marshal.a{beg}.name:{}", self.name());
@@ -2,15 +2,36 @@ use crate::rbytes::rbuffer::RBuffer;
 use crate::rbytes::wbuffer::WBuffer;
 use crate::rbytes::{RVersioner, Unmarshaler};
 use crate::root::traits::Object;
-use crate::{factory_all_for_register_impl, Marshaler};
-use crate::{rbase, rvers};
+use crate::{factory_fn_register_impl, Marshaler};
+use crate::{rbase, root, rvers};
 
+/// Rust equivalent of ROOT `TObjString`, a string which can be written in a file or in a
+/// container, see [RootFile::put](crate::RootFile::put).
 #[derive(Default)]
 pub struct TObjString {
     obj: rbase::Object,
     str: String,
 }
 
+impl TObjString {
+    pub fn new<S: Into<String>>(str: S) -> Self {
+        Self {
+            str: str.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl root::traits::Named for TObjString {
+    fn name(&self) -> &'_ str {
+        &self.str
+    }
+
+    fn title(&self) -> &'_ str {
+        "Collectable string class"
+    }
+}
+
 impl ToString for TObjString {
     fn to_string(&self) -> String {
         self.str.clone()
@@ -18,17 +39,21 @@ impl ToString for TObjString {
 }
 
 impl Marshaler for TObjString {
-    fn marshal(&self, _w: &mut WBuffer) -> crate::rbytes::Result<i64> {
-        todo!()
+    fn marshal(&self, w: &mut WBuffer) -> crate::rbytes::Result<i64> {
+        let hdr = w.write_header(self.class(), Self::rversion(self))?;
+        w.write_object(&self.obj)?;
+        w.write_string(&self.str)?;
+        w.set_header(hdr)
+    }
+}
+
+impl RVersioner for TObjString {
+    fn rversion(&self) -> i16 {
+        rvers::OBJ_STRING
     }
 }
-factory_all_for_register_impl!(TObjString, "TObjString", rvers::OBJ_STRING);
-//
-// impl RVersioner for ObjString {
-//     fn rversion() -> i16 {
-//         rvers::OBJ_STRING
-//     }
-// }
+
+factory_fn_register_impl!(TObjString, "TObjString");
 
 impl Unmarshaler for TObjString {
     fn unmarshal(&mut self, r: &mut RBuffer) -> crate::rbytes::Result<()> {
@@ -32,7 +32,7 @@ pub(crate) struct Header {
 
 /// RVersioner is the interface implemented by an object that
 /// can tell the ROOT system what is its current version.
-pub(crate) trait RVersioner {
+pub trait RVersioner {
     fn rversion(&self) -> i16;
 }
 
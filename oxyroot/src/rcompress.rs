@@ -1,13 +1,37 @@
-use flate2::read::ZlibDecoder;
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, ZlibEncoder};
+use flate2::Compression;
+use lz4::block::compress as LZ4_compress;
 use lz4::block::decompress_to_buffer as LZ4_decompress_to_buffer;
-use std::io::Read;
+use lz4::block::CompressionMode as LZ4CompressionMode;
+use std::io::{Read, Write};
+use xxhash_rust::xxh64::xxh64;
 use xz2::read::XzDecoder;
+use xz2::stream::{Check, Stream};
+use xz2::write::XzEncoder;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
+    /// A block header announces more compressed bytes than what is left in the input
+    TruncatedBlock {
+        expected: usize,
+        available: usize,
+    },
+    /// A block does not decompress to the size announced in its header
+    SizeMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// Compression algorithm not known by oxyroot, from a block header or compression settings
+    UnknownAlgorithm(String),
+    /// The xxhash64 checksum of a LZ4 block does not match its content
+    ChecksumMismatch {
+        expected: u64,
+        got: u64,
+    },
 }
 
 impl From<std::io::Error> for Error {
@@ -24,7 +48,6 @@ const HEADER_SIZE: usize = 9;
 // - the size of the compressed data
 // where each size is saved on 3 bytes, the maximal size
 // of each block can not be bigger than 16Mb.
-#[allow(dead_code)]
 const K_MAX_COMPRESSED_BLOCK_SIZE: usize = 0xffffff;
 
 #[allow(dead_code)]
@@ -41,6 +64,68 @@ enum Kind {
     UndefinedCompression = 6,
 }
 
+/// Compression algorithms available when writing ROOT files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    Zlib = 1,
+    Lzma = 2,
+    LZ4 = 4,
+    Zstd = 5,
+}
+
+/// Algorithm and level used to compress data written in a ROOT file, equivalent of ROOT's
+/// [`RCompressionSetting`](https://root.cern/doc/master/structROOT_1_1RCompressionSetting.html).
+///
+/// A level of 0 means no compression. Default is zlib with level 1, the default of ROOT.
+///
+/// ```
+/// use oxyroot::{CompressionAlgorithm, CompressionSettings};
+/// let settings = CompressionSettings::new(CompressionAlgorithm::LZ4, 4);
+/// assert_eq!(settings.to_root(), 404);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionSettings {
+    algorithm: CompressionAlgorithm,
+    level: u32,
+}
+
+impl CompressionSettings {
+    /// Levels above 9 are clamped to 9.
+    pub fn new(algorithm: CompressionAlgorithm, level: u32) -> Self {
+        Self {
+            algorithm,
+            level: level.min(9),
+        }
+    }
+
+    /// Settings storing data as is.
+    pub fn uncompressed() -> Self {
+        Self::new(CompressionAlgorithm::Zlib, 0)
+    }
+
+    pub fn algorithm(&self) -> CompressionAlgorithm {
+        self.algorithm
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Settings encoded as ROOT does, ie `algorithm * 100 + level`.
+    pub fn to_root(&self) -> i32 {
+        if self.level == 0 {
+            return 0;
+        }
+        self.algorithm as i32 * 100 + self.level as i32
+    }
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self::new(CompressionAlgorithm::Zlib, 1)
+    }
+}
+
 // kindOf returns the kind of compression algorithm.
 fn kind_of(buf: &[u8]) -> Kind {
     match (buf[0] as char, buf[1] as char) {
@@ -55,57 +140,84 @@ fn kind_of(buf: &[u8]) -> Kind {
 }
 
 pub fn decompress(dst: &mut [u8], mut src: &[u8]) -> Result<usize> {
-    let _beg = 0;
+    let mut beg = 0;
     let mut end = 0;
-    let buflen = dst.len() as i64;
+    let buflen = dst.len();
     let mut hdr = [0_u8; HEADER_SIZE];
 
-    // let mut v =
-
     while end < buflen {
-        // let src = src.as_mut();
         src.read_exact(&mut hdr)?;
-        // let _ = src.read_exact(dst)?;
-        // let _ = src.read_exact(dst)?;
 
-        let _srcsz = hdr[3] as i64 | (hdr[4] as i64) << 8 | (hdr[5] as i64) << 16;
-        let tgtsz = hdr[6] as i64 | (hdr[7] as i64) << 8 | (hdr[8] as i64) << 16;
-        // let tgtsz = hdr[6]) | int64(hdr[7])<<8 | int64(hdr[8])<<16
+        let srcsz = hdr[3] as usize | (hdr[4] as usize) << 8 | (hdr[5] as usize) << 16;
+        let tgtsz = hdr[6] as usize | (hdr[7] as usize) << 8 | (hdr[8] as usize) << 16;
         end += tgtsz;
 
-        match kind_of(hdr.as_ref()) {
-            Kind::Inherit => {
-                unimplemented!()
-            }
-            Kind::UseGlobal => {
-                unimplemented!()
-            }
+        if srcsz > src.len() {
+            return Err(Error::TruncatedBlock {
+                expected: srcsz,
+                available: src.len(),
+            });
+        }
+        if end > buflen {
+            return Err(Error::SizeMismatch {
+                expected: buflen,
+                got: end,
+            });
+        }
+
+        let block = &src[..srcsz];
+        let out = &mut dst[beg..end];
 
+        let n = match kind_of(hdr.as_ref()) {
             Kind::Zlib => {
-                let mut d = ZlibDecoder::new(src);
-                d.read_exact(dst.as_mut())?;
-                return Ok(0);
+                let mut d = ZlibDecoder::new(block);
+                d.read_exact(out)?;
+                tgtsz
             }
             Kind::Lzma => {
-                let mut d = XzDecoder::new(src);
-                d.read_exact(dst.as_mut())?;
+                let mut d = XzDecoder::new(block);
+                d.read_exact(out)?;
+                tgtsz
             }
             Kind::OldCompression => {
-                unimplemented!()
+                // legacy ROOT format: raw deflate stream, without zlib header
+                let mut d = DeflateDecoder::new(block);
+                d.read_exact(out)?;
+                tgtsz
             }
             Kind::LZ4 => {
-                LZ4_decompress_to_buffer(&src[8..], Some(dst.len() as i32), dst)?;
-                return Ok(0);
-                // let mut d = LZ4Decoder::new(src)?;
-                // d.read_exact(dst.as_mut())?;
+                // LZ4 blocks start with the xxhash64 checksum of the compressed data
+                if block.len() < 8 {
+                    return Err(Error::TruncatedBlock {
+                        expected: 8,
+                        available: block.len(),
+                    });
+                }
+                let (checksum, block) = block.split_at(8);
+                let expected = u64::from_be_bytes(checksum.try_into().unwrap());
+                let got = xxh64(block, 0);
+                if expected != got {
+                    return Err(Error::ChecksumMismatch { expected, got });
+                }
+                LZ4_decompress_to_buffer(block, Some(tgtsz as i32), out)?
             }
-            Kind::Zstd => {
-                unimplemented!()
-            }
-            Kind::UndefinedCompression => {
-                unimplemented!()
+            Kind::Zstd => zstd::bulk::decompress_to_buffer(block, out)?,
+            Kind::Inherit | Kind::UseGlobal | Kind::UndefinedCompression => {
+                return Err(Error::UnknownAlgorithm(
+                    String::from_utf8_lossy(&hdr[..2]).to_string(),
+                ));
             }
+        };
+
+        if n != tgtsz {
+            return Err(Error::SizeMismatch {
+                expected: tgtsz,
+                got: n,
+            });
         }
+
+        src = &src[srcsz..];
+        beg = end;
     }
 
     Ok(0)
@@ -127,24 +239,233 @@ fn root_compress_algo_level(algo: i32) -> (Kind, i32) {
 }
 
 pub fn compress(src: Vec<u8>, compression: i32) -> Result<Vec<u8>> {
-    assert_eq!(compression, 1);
-
     let (kind, level) = root_compress_algo_level(compression);
 
-    if kind == Kind::UseGlobal || level == 0 || src.len() < 512 {
+    if level <= 0 || src.len() < 512 {
         // no compression
-        // let mut dst = Vec::new();
-        //std::io::copy(src, &mut dst)?;
         return Ok(src);
     }
 
-    unimplemented!();
+    // ROOT compression levels run from 1 to 9
+    let level = level.min(9);
+
+    let nblocks = src.len() / K_MAX_COMPRESSED_BLOCK_SIZE + 1;
+    let mut dst = Vec::with_capacity(src.len() + nblocks * HEADER_SIZE);
+
+    for block in src.chunks(K_MAX_COMPRESSED_BLOCK_SIZE) {
+        let (hdr, payload) = match kind {
+            // ROOT falls back to its global default, which is zlib
+            Kind::UseGlobal | Kind::Zlib => {
+                let mut w = ZlibEncoder::new(Vec::new(), Compression::new(level as u32));
+                w.write_all(block)?;
+                ([b'Z', b'L', 8], w.finish()?)
+            }
+            Kind::Lzma => {
+                let stream = Stream::new_easy_encoder(level as u32, Check::Crc32)
+                    .map_err(std::io::Error::from)?;
+                let mut w = XzEncoder::new_stream(Vec::new(), stream);
+                w.write_all(block)?;
+                ([b'X', b'Z', 0], w.finish()?)
+            }
+            Kind::LZ4 => {
+                let mode = if level >= 4 {
+                    LZ4CompressionMode::HIGHCOMPRESSION(level)
+                } else {
+                    LZ4CompressionMode::DEFAULT
+                };
+                let compressed = LZ4_compress(block, Some(mode), false)?;
+
+                // LZ4 blocks start with the xxhash64 checksum of the compressed data
+                let mut payload = Vec::with_capacity(compressed.len() + 8);
+                payload.extend_from_slice(&xxh64(&compressed, 0).to_be_bytes());
+                payload.extend_from_slice(&compressed);
+                ([b'L', b'4', 1], payload)
+            }
+            Kind::Zstd => ([b'Z', b'S', 1], zstd::bulk::compress(block, level)?),
+            Kind::OldCompression => {
+                let mut w = DeflateEncoder::new(Vec::new(), Compression::new(level as u32));
+                w.write_all(block)?;
+                ([b'C', b'S', 8], w.finish()?)
+            }
+            Kind::Inherit | Kind::UndefinedCompression => {
+                return Err(Error::UnknownAlgorithm(compression.to_string()));
+            }
+        };
+
+        if payload.len() + HEADER_SIZE >= block.len() {
+            // compression does not pay off: ROOT stores the whole buffer uncompressed
+            return Ok(src);
+        }
+
+        let (srcsz, tgtsz) = (payload.len(), block.len());
+        dst.extend_from_slice(&hdr);
+        dst.extend_from_slice(&[srcsz as u8, (srcsz >> 8) as u8, (srcsz >> 16) as u8]);
+        dst.extend_from_slice(&[tgtsz as u8, (tgtsz >> 8) as u8, (tgtsz >> 16) as u8]);
+        dst.extend(payload);
+    }
+
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zstd_block(data: &[u8]) -> Vec<u8> {
+        let compressed = zstd::bulk::compress(data, 3).unwrap();
+        let (srcsz, tgtsz) = (compressed.len(), data.len());
+        let mut block = vec![
+            b'Z',
+            b'S',
+            1,
+            srcsz as u8,
+            (srcsz >> 8) as u8,
+            (srcsz >> 16) as u8,
+            tgtsz as u8,
+            (tgtsz >> 8) as u8,
+            (tgtsz >> 16) as u8,
+        ];
+        block.extend_from_slice(&compressed);
+        block
+    }
+
+    #[test]
+    fn decompress_zstd() -> Result<()> {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        let src = zstd_block(&data);
+
+        let mut dst = vec![0; data.len()];
+        decompress(&mut dst, &src)?;
+        assert_eq!(dst, data);
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_zstd_multi_blocks() -> Result<()> {
+        let first = (0..3000).map(|i| (i % 13) as u8).collect::<Vec<_>>();
+        let second = (0..2000).map(|i| (i % 7) as u8).collect::<Vec<_>>();
+        let mut src = zstd_block(&first);
+        src.extend(zstd_block(&second));
+
+        let mut dst = vec![0; first.len() + second.len()];
+        decompress(&mut dst, &src)?;
+        assert_eq!(&dst[..first.len()], first.as_slice());
+        assert_eq!(&dst[first.len()..], second.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_zstd_truncated() {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        let src = zstd_block(&data);
+
+        let mut dst = vec![0; data.len()];
+        let ret = decompress(&mut dst, &src[..src.len() - 4]);
+        assert!(matches!(ret, Err(Error::TruncatedBlock { .. })));
+    }
+
+    fn roundtrip(compression: i32, data: &[u8]) -> Result<()> {
+        let src = compress(data.to_vec(), compression)?;
+        assert!(src.len() < data.len());
+
+        let mut dst = vec![0; data.len()];
+        decompress(&mut dst, &src)?;
+        assert_eq!(dst, data);
+        Ok(())
+    }
+
+    #[test]
+    fn compress_roundtrip() -> Result<()> {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        for compression in [1, 101, 109, 201, 401, 409, 501] {
+            roundtrip(compression, &data)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn compress_multi_blocks() -> Result<()> {
+        let data = (0..K_MAX_COMPRESSED_BLOCK_SIZE + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        for (compression, algo) in [(101, b"ZL"), (201, b"XZ"), (401, b"L4"), (505, b"ZS")] {
+            let src = compress(data.clone(), compression)?;
+            assert_eq!(&src[..2], algo);
+            roundtrip(compression, &data)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn compress_lz4_checksum() -> Result<()> {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        let src = compress(data, 404)?;
+        assert_eq!(&src[..3], &[b'L', b'4', 1]);
+        assert_eq!(
+            src[HEADER_SIZE..HEADER_SIZE + 8],
+            xxh64(&src[HEADER_SIZE + 8..], 0).to_be_bytes()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_lz4_bad_checksum() -> Result<()> {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        let mut src = compress(data.clone(), 404)?;
+        src[HEADER_SIZE] ^= 0xff;
+
+        let mut dst = vec![0; data.len()];
+        let ret = decompress(&mut dst, &src);
+        assert!(matches!(ret, Err(Error::ChecksumMismatch { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn decompress_old_compression() -> Result<()> {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        let src = compress(data.clone(), 301)?;
+        assert_eq!(&src[..3], &[b'C', b'S', 8]);
+
+        // the legacy format has no zlib header
+        let mut raw = Vec::new();
+        DeflateDecoder::new(&src[HEADER_SIZE..]).read_to_end(&mut raw)?;
+        assert_eq!(raw, data);
+
+        roundtrip(301, &data)
+    }
+
+    #[test]
+    fn decompress_unknown_algorithm() -> Result<()> {
+        let data = (0..4096).map(|i| (i % 17) as u8).collect::<Vec<_>>();
+        let mut src = compress(data.clone(), 101)?;
+        src[..2].copy_from_slice(b"QQ");
 
-    // let mut dst = Vec::new();
-    // {
-    //     let mut w = flate2::write::ZlibEncoder::new(&mut dst, flate2::Compression::default());
-    //     std::io::copy(&mut src, &mut w)?;
-    // }
-    //
-    // Ok(dst)
+        let mut dst = vec![0; data.len()];
+        let ret = decompress(&mut dst, &src);
+        assert!(matches!(ret, Err(Error::UnknownAlgorithm(magic)) if magic == "QQ"));
+
+        let ret = compress(data, 901);
+        assert!(matches!(ret, Err(Error::UnknownAlgorithm(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn compress_small_or_incompressible() -> Result<()> {
+        let small = vec![0; 100];
+        assert_eq!(compress(small.clone(), 101)?, small);
+
+        let mut x = 0x2545f491_u32;
+        let noise = (0..4096)
+            .map(|_| {
+                // xorshift32
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(compress(noise.clone(), 101)?, noise);
+        assert_eq!(compress(noise.clone(), 100)?, noise);
+        Ok(())
+    }
 }
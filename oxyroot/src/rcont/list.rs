@@ -2,7 +2,8 @@ use crate::rbase;
 use crate::rbytes::rbuffer::RBuffer;
 use crate::rbytes::wbuffer::WBuffer;
 use crate::rbytes::{
-    ensure_maximum_supported_version, ensure_minimum_supported_version, Marshaler, Unmarshaler,
+    ensure_maximum_supported_version, ensure_minimum_supported_version, Marshaler, RVersioner,
+    Unmarshaler,
 };
 use crate::root::traits;
 use crate::root::traits::Object;
@@ -36,6 +37,13 @@ impl<'a> WriterList<'a> {
     pub(crate) fn push(&mut self, obj: &'a dyn FactoryItemWrite, ptr: usize) {
         self.objs.push((obj, ptr));
     }
+
+    /// Add `obj` at the end of the list, the list is written with
+    /// [RootFile::put](crate::RootFile::put).
+    pub fn add(&mut self, obj: &'a dyn FactoryItemWrite) {
+        let ptr = obj as *const dyn FactoryItemWrite as *const () as usize;
+        self.push(obj, ptr);
+    }
 }
 
 impl ReaderList {
@@ -182,3 +190,9 @@ impl Marshaler for WriterList<'_> {
         Ok(w.pos() - beg)
     }
 }
+
+impl RVersioner for WriterList<'_> {
+    fn rversion(&self) -> i16 {
+        rvers::LIST
+    }
+}
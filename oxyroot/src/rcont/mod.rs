@@ -4,4 +4,4 @@ pub mod list;
 pub mod objarray;
 pub mod tmap;
 
-pub use tmap::TMap;
+pub use tmap::{TMap, WriterMap};
@@ -1,7 +1,9 @@
+use crate::rbytes::wbuffer::WBuffer;
+use crate::rbytes::RVersioner;
 use crate::root::traits;
-use crate::rtypes::factory::{Factory, FactoryBuilder};
+use crate::rtypes::factory::{Factory, FactoryBuilder, FactoryItemWrite};
 use crate::rtypes::FactoryItemRead;
-use crate::{rbase, Object, RBuffer, Unmarshaler};
+use crate::{rbase, rvers, Marshaler, Object, RBuffer, Unmarshaler};
 use log::trace;
 use std::collections::HashMap;
 
@@ -93,3 +95,66 @@ impl Unmarshaler for TMap {
         Ok(())
     }
 }
+
+/// Map to be written with [RootFile::put](crate::RootFile::put), read back as a [TMap]. Keys
+/// and values are borrowed until the map is written, keys are usually
+/// [TObjString](crate::rbase::TObjString).
+#[derive(Default, Debug)]
+pub struct WriterMap<'a> {
+    obj: rbase::Object,
+    objs: Vec<(&'a dyn FactoryItemWrite, &'a dyn FactoryItemWrite)>,
+}
+
+impl<'a> WriterMap<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &'a dyn FactoryItemWrite, value: &'a dyn FactoryItemWrite) {
+        self.objs.push((key, value));
+    }
+}
+
+impl Object for WriterMap<'_> {
+    fn class(&self) -> &'_ str {
+        "TMap"
+    }
+}
+
+impl traits::Named for WriterMap<'_> {
+    fn name(&self) -> &'_ str {
+        "TMap"
+    }
+
+    fn title(&self) -> &'_ str {
+        "A (key,value) map"
+    }
+}
+
+impl Marshaler for WriterMap<'_> {
+    fn marshal(&self, w: &mut WBuffer) -> crate::rbytes::Result<i64> {
+        let beg = w.pos();
+        trace!(";TMap.marshal.beg:{:?}", beg);
+        let hdr = w.write_header(self.class(), Self::rversion(self))?;
+
+        self.obj.marshal(w)?;
+        w.write_string("")?;
+        w.write_i32(self.objs.len() as i32)?;
+
+        for (key, value) in self.objs.iter() {
+            for obj in [*key, *value] {
+                let ptr = obj as *const dyn FactoryItemWrite as *const () as usize;
+                w.write_object_any(obj, ptr)?;
+            }
+        }
+
+        w.set_header(hdr)?;
+        Ok(w.pos() - beg)
+    }
+}
+
+impl RVersioner for WriterMap<'_> {
+    fn rversion(&self) -> i16 {
+        rvers::MAP
+    }
+}
@@ -294,6 +294,8 @@ impl Unmarshaler for StreamerInfo {
             });
         }
 
+        // ids are used as addresses when written back, see Marshaler
+        self.id = ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         r.read_object(&mut self.named)?;
 
         self.chksum = r.read_u32()?;
@@ -569,6 +571,8 @@ impl Unmarshaler for StreamerElement {
             });
         }
 
+        // ids are used as addresses when written back, see Marshaler
+        self.id = ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         r.read_object(&mut self.named)?;
 
         // = Enum::from_i32();
@@ -1,9 +1,9 @@
-use crate::rdict::error::Result;
+use crate::rbytes::RVersioner;
+use crate::rdict::error::{Error, Result};
 use crate::rdict::streamers::streamers_db_generated::populate_db;
 use crate::rdict::StreamerInfo;
 use crate::riofs::dir::TDirectoryFile;
 use crate::root::traits::Named;
-use crate::rtypes::factory::FactoryItemWrite;
 use lazy_static::lazy_static;
 use log::trace;
 use std::collections::HashMap;
@@ -64,7 +64,7 @@ lazy_static! {
 
 pub(crate) fn streamer_info_from<T>(obj: &T, _dir: &mut TDirectoryFile) -> Result<StreamerInfo>
 where
-    T: FactoryItemWrite,
+    T: Named + RVersioner,
 {
     let typename = obj.class();
     let _cxxtype = obj.class();
@@ -77,7 +77,7 @@ where
 
     let si = DBSTREAMER
         .get(typename, vers)
-        .expect(&format!("can not extract streamer for '{}'", typename));
+        .ok_or_else(|| Error::StreamerCanNotFoundClass(typename.to_string()))?;
 
     trace!(";streamer_info_from.si: {:?}", si);
 
@@ -91,3 +91,95 @@ impl FreeList {
         self.0.len()
     }
 }
+
+impl FreeList {
+    /// Add the free bytes from `first` to `last` (included), merged with the adjacent
+    /// segments, as ROOT `TFree::AddFree`. Segments are kept sorted. Returns the index of the
+    /// segment containing the new free bytes.
+    pub(crate) fn add(&mut self, first: i64, last: i64) -> usize {
+        trace!(";FreeList.add.first:{:?}", first);
+        trace!(";FreeList.add.last:{:?}", last);
+        let segs = &mut self.0;
+        let i = segs.partition_point(|s| s.last < first - 1);
+
+        if i < segs.len() && segs[i].last == first - 1 {
+            segs[i].last = segs[i].last.max(last);
+            if i + 1 < segs.len() && segs[i + 1].first <= segs[i].last + 1 {
+                let next = segs.remove(i + 1);
+                segs[i].last = segs[i].last.max(next.last);
+            }
+            return i;
+        }
+
+        if i < segs.len() && segs[i].first <= last + 1 {
+            segs[i].first = segs[i].first.min(first);
+            segs[i].last = segs[i].last.max(last);
+            return i;
+        }
+
+        segs.insert(i, FreeSegments::new(first, last));
+        i
+    }
+
+    /// Index of the smallest segment where `nbytes` can be written, the trailing segment after
+    /// the end of file excepted. The segment is either exactly `nbytes` long or leaves room for
+    /// the 4 bytes marking the remaining gap.
+    pub(crate) fn best_free(&self, nbytes: i64) -> Option<usize> {
+        let n = self.0.len().checked_sub(1)?;
+        self.0[..n]
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, s.last - s.first + 1))
+            .filter(|(_, size)| *size == nbytes || *size >= nbytes + 4)
+            .min_by_key(|(_, size)| *size)
+            .map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(list: &FreeList) -> Vec<(i64, i64)> {
+        list.0.iter().map(|s| (s.first, s.last)).collect()
+    }
+
+    #[test]
+    fn add_merges_segments() {
+        let mut list = FreeList::default();
+        list.append(FreeSegments::new(1000, kStartBigFile));
+
+        assert_eq!(list.add(100, 199), 0);
+        assert_eq!(list.add(400, 499), 1);
+        assert_eq!(
+            segments(&list),
+            [(100, 199), (400, 499), (1000, kStartBigFile)]
+        );
+
+        // after, before and between two segments
+        assert_eq!(list.add(200, 249), 0);
+        assert_eq!(list.add(350, 399), 1);
+        assert_eq!(list.add(250, 349), 0);
+        assert_eq!(segments(&list), [(100, 499), (1000, kStartBigFile)]);
+
+        // merged with the trailing segment
+        assert_eq!(list.add(500, 999), 0);
+        assert_eq!(segments(&list), [(100, kStartBigFile)]);
+    }
+
+    #[test]
+    fn best_free_segment() {
+        let mut list = FreeList::default();
+        list.add(100, 199);
+        list.add(300, 349);
+        list.add(400, 409);
+        list.add(1000, kStartBigFile);
+
+        assert_eq!(list.best_free(10), Some(2));
+        assert_eq!(list.best_free(20), Some(1));
+        // not enough room left for the gap marker
+        assert_eq!(list.best_free(48), Some(0));
+        assert_eq!(list.best_free(50), Some(1));
+        assert_eq!(list.best_free(200), None);
+    }
+}
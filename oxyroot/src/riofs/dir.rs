@@ -1,22 +1,24 @@
-use crate::riofs::file::{RootFile, RootFileReader};
+use crate::riofs::file::{RootFile, RootFileReader, RootFileStreamerInfoContext};
 
 use crate::rbase::named::Named as ObjNamed;
 use crate::rbytes::rbuffer::RBuffer;
-use crate::rbytes::{Marshaler, StreamerInfoContext, Unmarshaler};
+use crate::rbytes::{Marshaler, RVersioner, StreamerInfoContext, Unmarshaler};
+use crate::riofs::consts::kStartBigFile;
 use crate::riofs::key::Key;
-use crate::riofs::utils::{datetime_to_u32, decode_name_cycle};
+use crate::riofs::utils::{datetime_to_u32, decode_name_cycle, u32_to_datetime};
 use crate::root::traits::Named;
 use crate::root::traits::Object;
 
 use crate::rbytes::wbuffer::WBuffer;
 use crate::riofs::{utils, Error, Result};
-use crate::root::traits;
 use chrono::{DateTime, Utc};
 use log::trace;
 use uuid::Uuid;
 
+use crate::rtree::tree::ReaderTree;
+use crate::rtypes::factory::FACTORY;
 use crate::rtypes::FactoryItemRead;
-use crate::rvers;
+use crate::{factory_fn_register_impl, rvers};
 
 #[derive(Clone)]
 pub struct TDirectory {
@@ -45,7 +47,7 @@ impl TDirectory {
 pub struct TDirectoryFile {
     ctime: DateTime<Utc>,
     mtime: DateTime<Utc>, //
-    n_bytes_keys: i32,
+    pub(crate) n_bytes_keys: i32,
     pub(crate) n_bytes_name: i32,
     // seekdir: i64,
     // seekparent: i64,
@@ -56,6 +58,8 @@ pub struct TDirectoryFile {
     pub(crate) seek_keys: i64,
     class_name: String,
     pub(crate) keys: Vec<Key>,
+    // subdirectories created when writing
+    pub(crate) dirs: Vec<TDirectoryFile>,
 }
 
 impl Default for TDirectoryFile {
@@ -72,13 +76,18 @@ impl Default for TDirectoryFile {
             seek_keys: 0,
             class_name: String::new(),
             keys: Vec::new(),
+            dirs: Vec::new(),
         }
     }
 }
 
-impl traits::Object for TDirectoryFile {
-    fn class(&self) -> &'_ str {
-        "TDirectoryFile"
+impl Named for TDirectoryFile {
+    fn name(&self) -> &'_ str {
+        &self.dir.named.name
+    }
+
+    fn title(&self) -> &'_ str {
+        &self.dir.named.title
     }
 }
 
@@ -90,6 +99,11 @@ impl TDirectoryFile {
     }
 
     pub fn read_keys(&mut self, file: &mut RootFile) -> Result<()> {
+        let mut reader = file.reader()?.clone();
+        self.read_keys_from(&mut reader)
+    }
+
+    pub(crate) fn read_keys_from(&mut self, file: &mut RootFileReader) -> Result<()> {
         if self.seek_keys <= 0 {
             return Err(Error::DirectoryNegativeSeekKeys(self.seek_keys));
         }
@@ -191,14 +205,36 @@ impl TDirectoryFile {
     }
 
     fn save(&mut self, file: &mut RootFile) -> Result<()> {
+        for dir in self.dirs.iter_mut() {
+            trace!(";TDirectoryFile.save.dir:{:?}", dir.name());
+            dir.close(file)?;
+        }
         self.save_keys(file)?;
         self.write_header(file)?;
 
-        // TODO: implement case where we have sub dirs
-
         Ok(())
     }
 
+    /// Cycles of the keys named `name`, in increasing order.
+    pub(crate) fn cycles(&self, name: &str) -> Vec<i32> {
+        let mut cycles = self
+            .keys
+            .iter()
+            .filter(|k| k.name() == name)
+            .map(|k| k.cycle())
+            .collect::<Vec<_>>();
+        cycles.sort();
+        cycles
+    }
+
+    pub(crate) fn subdir(&self, name: &str) -> Option<&TDirectoryFile> {
+        self.dirs.iter().find(|d| d.name() == name)
+    }
+
+    pub(crate) fn subdir_mut(&mut self, name: &str) -> Option<&mut TDirectoryFile> {
+        self.dirs.iter_mut().find(|d| d.name() == name)
+    }
+
     fn save_keys(&mut self, file: &mut RootFile) -> Result<()> {
         trace!(";TDirectoryFile.save_keys:{:?}", true);
         trace!(
@@ -207,6 +243,14 @@ impl TDirectoryFile {
         );
         trace!(";TDirectoryFile.save_keys.keys.len:{:?}", self.keys.len());
 
+        // keys written by a previous session, in update mode
+        if self.seek_keys > 0 {
+            file.make_free(
+                self.seek_keys,
+                self.seek_keys + self.n_bytes_keys as i64 - 1,
+            )?;
+        }
+
         let mut n_bytes = 4;
 
         if file.is_big_file() {
@@ -232,6 +276,7 @@ impl TDirectoryFile {
             n_bytes,
             file,
         )?;
+        hdr.set_seek_pdir(self.seek_dir);
 
         let mut buf = WBuffer::new(0);
         buf.write_i32(self.keys.len() as i32)?;
@@ -291,7 +336,78 @@ impl TDirectoryFile {
         Ok(())
     }
 
+    /// Directory at `path`, relative to this one. Components of `path` are separated by `/`.
+    pub(crate) fn get_directory(
+        &self,
+        path: &str,
+        file: &mut RootFileReader,
+        ctx: Option<&dyn StreamerInfoContext>,
+    ) -> Result<TDirectoryFile> {
+        trace!(";TDirectoryFile.get_directory.path:{:?}", path);
+        let mut dir = self.clone();
+        for name in path.split('/').filter(|s| !s.is_empty()) {
+            let obj = dir.get_object_in_dir(name, file, ctx)?;
+            dir = *obj
+                .downcast::<TDirectoryFile>()
+                .map_err(|_| Error::NotADirectory(name.to_string()))?;
+        }
+        Ok(dir)
+    }
+
+    /// Object at `path`, relative to this directory, as `dir/sub/name;cycle`.
     pub(crate) fn get_object(
+        &self,
+        path: &str,
+        file: &mut RootFileReader,
+        ctx: Option<&dyn StreamerInfoContext>,
+    ) -> Result<Box<dyn FactoryItemRead>> {
+        match path.rsplit_once('/') {
+            None => self.get_object_in_dir(path, file, ctx),
+            Some((dir, namecycle)) => {
+                let dir = self.get_directory(dir, file, ctx)?;
+                dir.get_object_in_dir(namecycle, file, ctx)
+            }
+        }
+    }
+
+    /// Object at `path` downcasted to `T`, the type registered in the factory for the class of
+    /// the key. On mismatch, the error reports the class registered for `T`.
+    pub(crate) fn get<T: FactoryItemRead>(
+        &self,
+        path: &str,
+        file: &mut RootFileReader,
+        ctx: Option<&dyn StreamerInfoContext>,
+    ) -> Result<T> {
+        let objet = self.get_object(path, file, ctx)?;
+        let key_class = objet.class().to_string();
+        trace!(";TDirectoryFile.get.key_class:{:?}", key_class);
+        match objet.downcast::<T>() {
+            Ok(objet) => Ok(*objet),
+            Err(_) => Err(Error::KeyClassMismatch {
+                key: path.to_string(),
+                key_class,
+                obj_class: FACTORY
+                    .class_of::<T>()
+                    .map_or_else(|| std::any::type_name::<T>().to_string(), str::to_string),
+            }),
+        }
+    }
+
+    pub(crate) fn get_tree(
+        &self,
+        path: &str,
+        file: &RootFileReader,
+        sinfos: &RootFileStreamerInfoContext,
+    ) -> Result<ReaderTree> {
+        let mut reader = file.clone();
+        let mut objet: ReaderTree = self.get(path, &mut reader, Some(sinfos))?;
+
+        objet.set_reader(Some(reader));
+        objet.set_streamer_info(sinfos.clone());
+        Ok(objet)
+    }
+
+    fn get_object_in_dir(
         &self,
         namecycle: &str,
         file: &mut RootFileReader,
@@ -306,22 +422,13 @@ impl TDirectoryFile {
         let mut keys = self
             .keys
             .iter()
-            .filter(|k| {
-                if k.name() == name {
-                    if cycle != 9999 {
-                        todo!();
-                    }
-                    true
-                } else {
-                    false
-                }
-            })
+            .filter(|k| k.name() == name && (cycle == 9999 || k.cycle() == cycle as i32))
             .collect::<Vec<_>>();
 
         let key = match keys.len() {
             0 => {
                 return Err(Error::KeyNotInFile {
-                    key: name.to_string(),
+                    key: namecycle.to_string(),
                     file: file.to_string(),
                 })
             }
@@ -372,11 +479,186 @@ impl TDirectoryFile {
     pub fn dir(&self) -> &TDirectory {
         &self.dir
     }
+    pub(crate) fn set_name_title(&mut self, name: &str, title: &str) {
+        self.dir.named.name = name.to_string();
+        self.dir.named.title = title.to_string();
+    }
     pub(crate) fn is_big_file(&self) -> bool {
         self.dir.rvers > 1000
     }
 }
 
+/// Directory of a [RootFile], equivalent of ROOT `TDirectoryFile`. Returned by
+/// [RootFile::get_directory](crate::RootFile::get_directory).
+pub struct Directory {
+    dir: TDirectoryFile,
+    path: String,
+    reader: RootFileReader,
+    sinfos: RootFileStreamerInfoContext,
+}
+
+impl Directory {
+    pub(crate) fn new(
+        dir: TDirectoryFile,
+        path: String,
+        reader: RootFileReader,
+        sinfos: RootFileStreamerInfoContext,
+    ) -> Self {
+        Self {
+            dir,
+            path,
+            reader,
+            sinfos,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.dir.name()
+    }
+
+    pub fn title(&self) -> &str {
+        self.dir.title()
+    }
+
+    /// Path of the directory in the file, as `dir/sub`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn keys_name(&self) -> impl Iterator<Item = &str> {
+        self.dir.keys().iter().map(|e| e.name())
+    }
+
+    pub fn keys(&self) -> Vec<Key> {
+        self.dir.keys().clone()
+    }
+
+    /// Cycles of the key `name`, in increasing order. Trees saved several times, for example by
+    /// `AutoSave`, have one key per cycle: the last cycle is read unless `name;cycle` is given.
+    pub fn cycles(&self, name: &str) -> Vec<i32> {
+        self.dir.cycles(name)
+    }
+
+    /// Tree at `path`, relative to this directory.
+    pub fn get_tree(&self, path: &str) -> Result<ReaderTree> {
+        self.dir.get_tree(path, &self.reader, &self.sinfos)
+    }
+
+    /// Object at `path`, relative to this directory, see [RootFile::get].
+    pub fn get<T: FactoryItemRead>(&self, path: &str) -> Result<T> {
+        let mut reader = self.reader.clone();
+        self.dir.get(path, &mut reader, Some(&self.sinfos))
+    }
+
+    /// Subdirectory at `path`, relative to this directory.
+    pub fn get_directory(&self, path: &str) -> Result<Directory> {
+        let mut reader = self.reader.clone();
+        let dir = self
+            .dir
+            .get_directory(path, &mut reader, Some(&self.sinfos))?;
+        let path = [self.path.as_str(), path]
+            .iter()
+            .flat_map(|p| p.split('/'))
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(Directory::new(dir, path, reader, self.sinfos.clone()))
+    }
+}
+
+/// Subdirectory of a file being written, returned by [RootFile::mkdir] and
+/// [RootFile::mkdir_all].
+///
+/// Objects are written in this directory with [put](DirectoryWriter::put) and trees with
+/// [WriterTree::write](crate::WriterTree::write). The file can not be used while the handle is
+/// alive, it is closed with [RootFile::close] once the handle is dropped.
+///
+/// ```
+/// use oxyroot::{RootFile, WriterTree};
+/// {
+///     let mut f = RootFile::create("/tmp/mkdir.root").expect("Can not create file");
+///     for category in ["signal", "background"] {
+///         let mut dir = f.mkdir(category).expect("Can not create directory");
+///         let mut tree = WriterTree::new("events");
+///         tree.new_branch("n", 0..10_i32);
+///         tree.write(&mut dir).expect("Can not write tree");
+///     }
+///     f.close().expect("Can not close file");
+/// }
+///
+/// let mut f = RootFile::open("/tmp/mkdir.root").expect("Can not open file");
+/// assert_eq!(f.get_tree("signal/events").unwrap().entries(), 10);
+/// ```
+pub struct DirectoryWriter<'a> {
+    file: &'a mut RootFile,
+    previous: Vec<String>,
+}
+
+impl<'a> DirectoryWriter<'a> {
+    pub(crate) fn new(file: &'a mut RootFile, previous: Vec<String>) -> Self {
+        Self { file, previous }
+    }
+
+    /// Path of the directory in the file, as `dir/sub`.
+    pub fn path(&self) -> String {
+        self.file.cwd_path().join("/")
+    }
+
+    /// Write `obj` in this directory, as [RootFile::put].
+    pub fn put<T>(&mut self, name: &str, obj: &T) -> Result<()>
+    where
+        T: Marshaler + Named + RVersioner,
+    {
+        self.file.put(name, obj)
+    }
+
+    /// Create the directory `path` (as `sub/subsub`) in this directory, as [RootFile::mkdir].
+    pub fn mkdir(&mut self, path: &str) -> Result<DirectoryWriter<'_>> {
+        self.file.mkdir(path)
+    }
+
+    /// Same as [mkdir](DirectoryWriter::mkdir), but create missing parents and accept existing
+    /// directories.
+    pub fn mkdir_all(&mut self, path: &str) -> Result<DirectoryWriter<'_>> {
+        self.file.mkdir_all(path)
+    }
+}
+
+/// Where a tree is written by [WriterTree::write](crate::WriterTree::write): a [RootFile] or a
+/// [DirectoryWriter].
+pub trait WriteDestination: sealed::Destination {}
+
+impl WriteDestination for RootFile {}
+impl WriteDestination for DirectoryWriter<'_> {}
+
+pub(crate) mod sealed {
+    use crate::riofs::dir::DirectoryWriter;
+    use crate::RootFile;
+
+    pub trait Destination {
+        /// File written, with its current directory set to the destination.
+        fn file_mut(&mut self) -> &mut RootFile;
+    }
+
+    impl Destination for RootFile {
+        fn file_mut(&mut self) -> &mut RootFile {
+            self
+        }
+    }
+
+    impl Destination for DirectoryWriter<'_> {
+        fn file_mut(&mut self) -> &mut RootFile {
+            self.file
+        }
+    }
+}
+
+impl Drop for DirectoryWriter<'_> {
+    fn drop(&mut self) {
+        self.file.set_cwd(std::mem::take(&mut self.previous));
+    }
+}
+
 impl Unmarshaler for TDirectoryFile {
     fn unmarshal(&mut self, r: &mut RBuffer) -> crate::rbytes::Result<()> {
         let version = r.read_i16()?;
@@ -385,8 +667,8 @@ impl Unmarshaler for TDirectoryFile {
         let ctime = r.read_u32()?;
         let mtime = r.read_u32()?;
 
-        let ctime = DateTime::from_timestamp(ctime as i64, 0).unwrap();
-        let mtime = DateTime::from_timestamp(mtime as i64, 0).unwrap();
+        let ctime = u32_to_datetime(ctime);
+        let mtime = u32_to_datetime(mtime);
         trace!("read ctime = {}", ctime);
 
         let n_bytes_keys = r.read_i32()?;
@@ -436,7 +718,16 @@ impl Unmarshaler for TDirectoryFile {
 impl Marshaler for TDirectoryFile {
     fn marshal(&self, w: &mut WBuffer) -> crate::rbytes::Result<i64> {
         let beg = w.pos();
-        let version = self.dir().rvers;
+        // as ROOT, seeks are written with 64 bits as soon as one of them is above kStartBigFile,
+        // the record has room for them, see record_size
+        let big = self.is_big_file()
+            || [self.seek_dir, self.seek_parent, self.seek_keys]
+                .iter()
+                .any(|&seek| seek > kStartBigFile);
+        let mut version = self.dir().rvers;
+        if big && version <= 1000 {
+            version += 1000;
+        }
         trace!(";TDirectoryFile.marshal.beg:{:?}", beg);
         trace!(";TDirectoryFile.marshal.version:{:?}", version);
         w.write_i16(version)?;
@@ -457,7 +748,7 @@ impl Marshaler for TDirectoryFile {
         );
         w.write_i32(self.n_bytes_name)?;
 
-        if self.is_big_file() {
+        if big {
             w.write_i64(self.seek_dir)?;
             w.write_i64(self.seek_parent)?;
             w.write_i64(self.seek_keys)?;
@@ -480,3 +771,5 @@ impl Marshaler for TDirectoryFile {
         Ok(end - beg)
     }
 }
+
+factory_fn_register_impl!(TDirectoryFile, "TDirectoryFile");
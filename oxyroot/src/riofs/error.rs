@@ -5,6 +5,7 @@ pub enum Error {
     TryFromSlice,
     Io(std::io::Error),
     FileHasAnIncorrectHeaderLength,
+    NotARootFile,
     CantReadDirectoryInfo {
         n_bytes_name_read: i32,
         n_bytes_name_min_allowed: i32,
@@ -17,7 +18,13 @@ pub enum Error {
         file: String,
     },
     CantLoadKeyPayload(String),
+    InvalidRecord {
+        seek: i64,
+        reason: String,
+    },
     ObjectNotInDirectory(String),
+    NotADirectory(String),
+    DirectoryAlreadyExists(String),
 
     DirectoryNegativeSeekKeys(i64),
     CantReadAmountOfBytesFromFile {
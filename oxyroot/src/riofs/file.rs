@@ -1,28 +1,41 @@
 use crate::rdict::streamers::db::streamer_info;
 use crate::riofs::consts;
 use crate::rtree::tree::reader::ReaderTree;
+use crate::rtree::tree::FriendElement;
 use crate::utils::is_cxx_builtin;
+use std::collections::BTreeSet;
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::ptr::addr_of;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::rbytes::rbuffer::RBuffer;
 use crate::rbytes::wbuffer::WBuffer;
-use crate::rbytes::{Marshaler, StreamerInfoContext};
+use crate::rbytes::{Marshaler, RVersioner, StreamerInfoContext};
+use crate::rcompress::CompressionSettings;
 use crate::rcont::list::{ReaderList, WriterList};
 use crate::rdict::streamers::db::streamer_info_from;
 use crate::rdict::{Streamer, StreamerInfo, Visitor};
 use crate::riofs::blocks::{FreeList, FreeSegments};
 use crate::riofs::consts::kStartBigFile;
-use crate::riofs::dir::TDirectoryFile;
+use crate::riofs::dir::{Directory, DirectoryWriter, TDirectoryFile};
+#[cfg(feature = "http")]
+use crate::riofs::http;
 use crate::riofs::key::Key;
-use crate::riofs::{Error, Result};
+use crate::riofs::reader::{MemReader, ReaderAt};
+use crate::riofs::recover::scan_records;
+use crate::riofs::utils::decode_name_cycle;
+use crate::riofs::verify::Verifier;
+#[cfg(feature = "xrootd")]
+use crate::riofs::xrootd;
+use crate::riofs::{Error, Result, VerifyReport};
 use crate::rmeta::ESTLType;
 use crate::root::traits::Named;
-use crate::rtypes::factory::FactoryItemWrite;
+use crate::rtree::branch::TBranch;
+use crate::rtree::cache::{plan_reads, BasketCache, BasketRange, BasketRegistration};
 use crate::rtypes::FactoryItemRead;
 use crate::utils::is_core_type;
 use crate::{rvers, Object};
@@ -57,15 +70,24 @@ impl RootFileHeader {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Clone)]
 pub(crate) struct RootFileReader {
-    path: PathBuf,
-    reader: Option<BufReader<File>>,
+    name: String,
+    reader: Option<Arc<dyn ReaderAt>>,
+    cache: Option<Arc<Mutex<BasketCache>>>,
+}
+
+impl Debug for RootFileReader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RootFileReader")
+            .field("name", &self.name)
+            .finish()
+    }
 }
 
 impl Display for RootFileReader {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.path.to_str().unwrap())?;
+        f.write_str(&self.name)?;
         Ok(())
     }
 }
@@ -76,28 +98,99 @@ impl RootFileReader {
         P: AsRef<Path>,
     {
         let f = File::open(path.as_ref())?;
-        let buf_reader = BufReader::new(f);
-        let reader = Self {
-            path: path.as_ref().to_path_buf(),
-            reader: Some(buf_reader),
-        };
-        Ok(reader)
+        let reader = Mutex::new(BufReader::new(f));
+        Ok(Self::from_reader_at(
+            path.as_ref().to_string_lossy().to_string(),
+            reader,
+        ))
+    }
+
+    pub(crate) fn from_reader_at<R>(name: String, reader: R) -> Self
+    where
+        R: ReaderAt + 'static,
+    {
+        Self {
+            name,
+            reader: Some(Arc::new(reader)),
+            cache: None,
+        }
+    }
+
+    /// Path of the file, or the name given to [RootFile::from_reader_at].
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Same source, with a new [BasketCache] shared by its clones.
+    pub(crate) fn with_basket_cache(&self) -> Self {
+        Self {
+            cache: Some(Arc::new(Mutex::new(BasketCache::default()))),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn register_baskets(&self, baskets: Vec<BasketRange>) -> Option<BasketRegistration> {
+        self.cache
+            .as_ref()
+            .map(|cache| BasketRegistration::new(cache.clone(), baskets))
     }
 
     pub(crate) fn read_at(&mut self, start: u64, len: u64) -> Result<Vec<u8>> {
+        let mut buf = vec![0; len as usize];
         self.reader
-            .as_mut()
+            .as_ref()
             .expect("ERROR")
-            .seek(SeekFrom::Start(start))?;
-        let mut buf = vec![0; len as usize];
-        self.reader.as_mut().expect("ERROR").read_exact(&mut buf)?;
+            .read_at(&mut buf, start)?;
         Ok(buf)
     }
-}
 
-impl Clone for RootFileReader {
-    fn clone(&self) -> Self {
-        RootFileReader::new(&self.path).unwrap()
+    pub(crate) fn read_ranges(&mut self, ranges: &[(u64, usize)]) -> Result<Vec<Vec<u8>>> {
+        Ok(self.reader.as_ref().expect("ERROR").read_ranges(ranges)?)
+    }
+
+    /// Read a basket, through the basket cache if any: baskets of other branches read along
+    /// are fetched in the same reads.
+    pub(crate) fn read_basket(&mut self, start: u64, len: u64) -> Result<Vec<u8>> {
+        let cache = match &self.cache {
+            None => return self.read_at(start, len),
+            Some(cache) => cache.clone(),
+        };
+        if let Some(buf) = cache.lock().unwrap().take(start, len as usize) {
+            return Ok(buf);
+        }
+
+        // the cache is not locked while reading, other threads may read baskets at the same time
+        let mut baskets = cache.lock().unwrap().plan(start, len as usize);
+        baskets.sort();
+        let reads = plan_reads(&baskets);
+        trace!(
+            ";RootFileReader.read_basket.{start}.reads:{:?}",
+            reads.len()
+        );
+        let data = self.read_ranges(&reads)?;
+
+        let mut ret = Vec::new();
+        let mut cache = cache.lock().unwrap();
+        let mut reads = reads.iter().zip(data.iter());
+        let mut read = reads.next();
+        for (seek, n) in baskets {
+            // baskets and reads are both sorted
+            while let Some(((beg, size), _)) = read {
+                if seek + n as u64 <= beg + *size as u64 {
+                    break;
+                }
+                read = reads.next();
+            }
+            let ((beg, _), buf) = read.expect("basket not covered by reads");
+            let off = (seek - beg) as usize;
+            let buf = buf[off..off + n].to_vec();
+            if seek == start {
+                ret = buf;
+            } else {
+                cache.insert(seek, buf);
+            }
+        }
+        Ok(ret)
     }
 }
 
@@ -121,6 +214,20 @@ impl RootFileWriter {
         Ok(reader)
     }
 
+    /// Open an existing file for writing, without truncating it.
+    pub(crate) fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let f = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path.as_ref())?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            writer: Some(BufWriter::new(f)),
+        })
+    }
+
     pub(crate) fn write_at(&mut self, buf: &[u8], start: u64) -> Result<()> {
         self.writer
             .as_mut()
@@ -129,6 +236,11 @@ impl RootFileWriter {
         self.writer.as_mut().expect("ERROR").write_all(buf)?;
         Ok(())
     }
+
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        self.writer.as_mut().expect("ERROR").flush()?;
+        Ok(())
+    }
 }
 
 // impl Clone for RootFileWriter {
@@ -141,10 +253,66 @@ impl RootFileWriter {
 enum RootFileInner {
     Reader(RootFileReader),
     Writer(RootFileWriter),
+    Update(RootFileReader, RootFileWriter),
     #[default]
     None,
 }
 
+/// Options used by [create_with_options](crate::RootFile::create_with_options).
+///
+/// ```
+/// use oxyroot::{CompressionAlgorithm, CompressionSettings, CreateOptions};
+/// let options = CreateOptions::default()
+///     .with_compression(CompressionSettings::new(CompressionAlgorithm::Zstd, 5));
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct CreateOptions {
+    compression: CompressionSettings,
+}
+
+impl CreateOptions {
+    /// Compression settings used for the objects written in the file, can be overridden by
+    /// [WriterTree](crate::WriterTree) for its baskets.
+    pub fn with_compression(mut self, compression: CompressionSettings) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// Options used by [open_with_options](crate::RootFile::open_with_options).
+///
+/// ```
+/// use oxyroot::OpenOptions;
+/// use std::time::Duration;
+/// let options = OpenOptions::default().with_timeout(Duration::from_secs(10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    timeout: Duration,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl OpenOptions {
+    /// Timeout to connect to the server and of each read or write on the connection, for files
+    /// read with the `http` or `xrootd` features. Default is 60 seconds, it must not be zero.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Timeout of remote files, see [with_timeout](OpenOptions::with_timeout).
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
 /// Rust equivalent of [`TFile`](https://root.cern/doc/master/classTFile.html).
 ///
 /// Can only read for now. Aims to be constructed with [open](crate::RootFile::open) method.
@@ -156,6 +324,11 @@ pub struct RootFile {
     sinfos: RootFileStreamerInfoContext,
     dir: TDirectoryFile,
     id: String,
+    // path of the directory where objects are written, see mkdir
+    cwd: Vec<String>,
+    // number of streamer infos read from the file in update mode, their dependencies are
+    // already in the file
+    n_sinfos_read: usize,
 }
 
 impl RootFile {
@@ -175,13 +348,127 @@ impl RootFile {
         self.header.end
     }
 
-    /// Open file, use [std::io::BufReader] for reading, so it can only handle local files for now.
+    /// Open file, use [std::io::BufReader] for reading local files.
+    /// See [from_bytes](crate::RootFile::from_bytes) and [from_reader](crate::RootFile::from_reader)
+    /// to read from other sources.
+    ///
+    /// With the `http` feature, `http://` and `https://` URLs are also accepted: the file is read
+    /// lazily with HTTP range requests. With the `xrootd` feature, `root://` URLs are read with the
+    /// XRootD protocol. Remote files are opened with the default [OpenOptions], see
+    /// [open_with_options](RootFile::open_with_options).
     pub fn open<P>(path: P) -> Result<Self>
     where
         P: AsRef<Path>,
     {
+        Self::open_with_options(path, OpenOptions::default())
+    }
+
+    /// Same as [open](RootFile::open), with options for remote files such as the timeout of the
+    /// connection.
+    pub fn open_with_options<P>(path: P, options: OpenOptions) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        #[cfg(feature = "http")]
+        if let Some(url) = path.as_ref().to_str().filter(|p| http::is_http_url(p)) {
+            return Self::from_reader_at(url, http::HttpReader::new(url, options.timeout)?);
+        }
+
+        #[cfg(feature = "xrootd")]
+        if let Some(url) = path.as_ref().to_str().filter(|p| xrootd::is_xrootd_url(p)) {
+            return Self::from_reader_at(url, xrootd::XRootDReader::new(url, options.timeout)?);
+        }
+
+        #[cfg(not(any(feature = "http", feature = "xrootd")))]
+        let _ = options;
+
         let reader = RootFileReader::new(path)?;
+        Self::open_reader(reader)
+    }
+
+    /// Open file from its content in memory.
+    ///
+    /**
+    ```
+    use oxyroot::RootFile;
+    let data = std::fs::read("examples/from_uproot/data/simple.root").expect("Can not read file");
+    let tree = RootFile::from_bytes(data).expect("Can not open file").get_tree("tree").unwrap();
+    assert_eq!(tree.entries(), 4);
+    ```
+     */
+    pub fn from_bytes<T>(data: T) -> Result<Self>
+    where
+        T: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        Self::from_reader_at("<memory>", MemReader(data))
+    }
+
+    /// Open file from a type implementing [Read] and [Seek], such as [std::io::Cursor].
+    pub fn from_reader<R>(reader: R) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        Self::from_reader_at("<reader>", Mutex::new(reader))
+    }
+
+    /// Open file from a custom source of bytes, `name` is only used to display the file.
+    pub fn from_reader_at<S, R>(name: S, reader: R) -> Result<Self>
+    where
+        S: AsRef<str>,
+        R: ReaderAt + 'static,
+    {
+        let reader = RootFileReader::from_reader_at(name.as_ref().to_string(), reader);
+        Self::open_reader(reader)
+    }
 
+    /// Open file with memory mapping, avoiding to copy baskets in user space.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, by this process or another one, while it is mapped.
+    #[cfg(feature = "mmap")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "mmap")))]
+    pub unsafe fn open_mmap<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let f = File::open(path.as_ref())?;
+        let mmap = memmap2::Mmap::map(&f)?;
+        Self::from_reader_at(path.as_ref().to_string_lossy(), MemReader(mmap))
+    }
+
+    /// Open an existing local file to add objects to it, as ROOT `TFile` `"UPDATE"` mode.
+    ///
+    /// Objects already in the file are kept and can still be read. New objects, written with
+    /// [WriterTree::write](crate::WriterTree::write) or in directories created with
+    /// [mkdir](RootFile::mkdir), are appended at the end of the file. The lists of keys, the
+    /// streamer infos and the free segments are written again by [close](RootFile::close).
+    pub fn open_update<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let reader = RootFileReader::new(path.as_ref())?;
+        let mut f = Self::open_reader(reader)?;
+        let reader = match std::mem::take(&mut f.inner) {
+            RootFileInner::Reader(r) => r,
+            _ => unreachable!(),
+        };
+        f.inner = RootFileInner::Update(reader, RootFileWriter::open(path.as_ref())?);
+        f.id = path.as_ref().to_string_lossy().to_string();
+        f.n_sinfos_read = f.sinfos.list().len();
+
+        // space after the end of file is kept as last free segment, see set_end
+        let end = f.end();
+        if !matches!(f.spans.vec().last(), Some(s) if s.first == end && s.last >= kStartBigFile) {
+            f.spans
+                .append(FreeSegments::new(end, kStartBigFile.max(end)));
+        }
+        trace!(";RootFile.open_update.end:{:?}", end);
+
+        Ok(f)
+    }
+
+    fn open_reader(reader: RootFileReader) -> Result<Self> {
         let inner = RootFileInner::Reader(reader);
 
         let mut f = RootFile {
@@ -194,7 +481,24 @@ impl RootFile {
         Ok(f)
     }
 
+    /// Create a new file, compressed with default [CompressionSettings], ie zlib with level 1.
     pub fn create<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::create_with_options(path, CreateOptions::default())
+    }
+
+    /// Create a new file with the provided [CreateOptions].
+    ///
+    /// ```
+    /// use oxyroot::{CompressionAlgorithm, CompressionSettings, CreateOptions, RootFile};
+    /// let options = CreateOptions::default()
+    ///     .with_compression(CompressionSettings::new(CompressionAlgorithm::Lzma, 9));
+    /// let file = RootFile::create_with_options("/tmp/lzma.root", options).expect("Can not create file");
+    /// assert_eq!(file.compression(), 209);
+    /// ```
+    pub fn create_with_options<P>(path: P, options: CreateOptions) -> Result<Self>
     where
         P: AsRef<Path>,
     {
@@ -208,7 +512,7 @@ impl RootFile {
             begin: consts::kBEGIN,
             end: consts::kBEGIN,
             units: 4,
-            compression: 1,
+            compression: options.compression.to_root(),
             ..Default::default()
         };
 
@@ -281,9 +585,33 @@ impl RootFile {
         Ok(f)
     }
 
-    pub(crate) fn put<T>(&mut self, name: &str, obj: &T) -> Result<()>
+    /// Write `obj` in the current directory, under `name` or under the name of the object when
+    /// `name` is empty. Writing an object with the name of an existing key adds a new cycle, the
+    /// class of the object must then be the one of the key.
+    ///
+    /// Objects are the types implementing [Marshaler], [Named] and
+    /// [RVersioner](crate::rbytes::RVersioner) whose class is known by ROOT, such as strings,
+    /// named objects and containers:
+    ///
+    /// ```
+    /// use oxyroot::rbase::{Named, TObjString};
+    /// use oxyroot::rcont::list::WriterList;
+    /// use oxyroot::RootFile;
+    /// {
+    ///     let mut f = RootFile::create("/tmp/put.root").expect("Can not create file");
+    ///     let note = TObjString::new("skimmed with cuts v3");
+    ///     f.put("provenance", &note).expect("Can not write string");
+    ///
+    ///     let cuts = [Named::new("pt", "pt > 20"), Named::new("eta", "|eta| < 2.5")];
+    ///     let mut list = WriterList::new();
+    ///     cuts.iter().for_each(|cut| list.add(cut));
+    ///     f.put("cuts", &list).expect("Can not write list");
+    ///     f.close().expect("Can not close file");
+    /// }
+    /// ```
+    pub fn put<T>(&mut self, name: &str, obj: &T) -> Result<()>
     where
-        T: FactoryItemWrite,
+        T: Marshaler + Named + RVersioner,
     {
         trace!(";TDirectoryFile.put.name:{:?}", name);
         if name.contains('/') {
@@ -298,7 +626,7 @@ impl RootFile {
 
         let mut cycle = 0;
 
-        for key in self.dir().keys().iter() {
+        for key in self.cwd().keys().iter() {
             if key.name() != name {
                 continue;
             }
@@ -334,10 +662,263 @@ impl RootFile {
         let mut key = Key::new_from_object(name, title, obj.class(), obj, self)?;
         key.set_cycle(cycle as i16);
         key.write_to_file(self.writer()?)?;
-        self.dir.keys.push(key);
+        self.cwd_mut().keys.push(key);
+        Ok(())
+    }
+
+    /// Create the directory `path` (as `dir/sub`) in the current directory, its parent
+    /// must exist. The returned handle is used to write objects in the new directory.
+    pub fn mkdir(&mut self, path: &str) -> Result<DirectoryWriter<'_>> {
+        self.mkdir_impl(path, false)
+    }
+
+    /// Same as [mkdir](RootFile::mkdir), but create missing parents and accept existing
+    /// directories.
+    pub fn mkdir_all(&mut self, path: &str) -> Result<DirectoryWriter<'_>> {
+        self.mkdir_impl(path, true)
+    }
+
+    fn mkdir_impl(&mut self, path: &str, all: bool) -> Result<DirectoryWriter<'_>> {
+        trace!(";RootFile.mkdir.path:{:?}", path);
+        self.writer()?;
+        let previous = self.cwd.clone();
+        let names = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        if names.is_empty() && !all {
+            return Err(Error::DirectoryAlreadyExists(path.to_string()));
+        }
+
+        for (i, name) in names.iter().enumerate() {
+            let last = i + 1 == names.len();
+            if let Err(e) = self.load_subdir(name) {
+                self.cwd = previous;
+                return Err(e);
+            }
+            let ret = match self.cwd().subdir(name) {
+                Some(_) if last && !all => Err(Error::DirectoryAlreadyExists(path.to_string())),
+                Some(_) => Ok(()),
+                None if !last && !all => Err(Error::ObjectNotInDirectory(names[..=i].join("/"))),
+                None => self.new_subdir(name),
+            };
+            if let Err(e) = ret {
+                self.cwd = previous;
+                return Err(e);
+            }
+            self.cwd.push(name.to_string());
+        }
+
+        Ok(DirectoryWriter::new(self, previous))
+    }
+
+    /// In update mode, load the subdirectory `name` already in the file so that objects can be
+    /// added to it.
+    fn load_subdir(&mut self, name: &str) -> Result<()> {
+        let cwd = self.cwd();
+        let exists = cwd
+            .keys()
+            .iter()
+            .any(|k| k.name() == name && k.class_name() == "TDirectoryFile");
+        if cwd.subdir(name).is_some() || !exists {
+            return Ok(());
+        }
+
+        let mut reader = self.reader()?.clone();
+        let dir = self
+            .cwd()
+            .get_directory(name, &mut reader, Some(&self.sinfos))?;
+        trace!(";RootFile.load_subdir.seek_dir:{:?}", dir.seek_dir);
+        self.cwd_mut().dirs.push(dir);
         Ok(())
     }
 
+    /// Delete the object at `path` (as `dir/name;cycle`), relative to the current directory.
+    /// Without cycle, the last cycle is deleted. Trees are deleted with their baskets and
+    /// directories with their content.
+    ///
+    /// The space used in the file is reused by the objects written next, so that replacing an
+    /// object in [update](RootFile::open_update) mode does not make the file grow.
+    pub fn delete(&mut self, path: &str) -> Result<()> {
+        trace!(";RootFile.delete.path:{:?}", path);
+        self.writer()?;
+        let previous = self.cwd.clone();
+        let (dir, namecycle) = path.rsplit_once('/').unwrap_or(("", path));
+
+        let mut ret = Ok(());
+        for name in dir.split('/').filter(|s| !s.is_empty()) {
+            ret = self.load_subdir(name);
+            if ret.is_ok() && self.cwd().subdir(name).is_none() {
+                ret = Err(Error::NotADirectory(name.to_string()));
+            }
+            if ret.is_err() {
+                break;
+            }
+            self.cwd.push(name.to_string());
+        }
+        if ret.is_ok() {
+            ret = self.delete_in_cwd(namecycle);
+        }
+
+        self.cwd = previous;
+        ret
+    }
+
+    fn delete_in_cwd(&mut self, namecycle: &str) -> Result<()> {
+        let (name, cycle) = decode_name_cycle(namecycle)?;
+        let key = self
+            .cwd()
+            .keys()
+            .iter()
+            .filter(|k| k.name() == name && (cycle == 9999 || k.cycle() == cycle as i32))
+            .max_by_key(|k| k.cycle())
+            .cloned()
+            .ok_or_else(|| Error::KeyNotInFile {
+                key: namecycle.to_string(),
+                file: self.id.clone(),
+            })?;
+        let namecycle = format!("{};{}", name, key.cycle());
+        trace!(";RootFile.delete.key:{:?}", namecycle);
+
+        match key.class_name() {
+            "TDirectoryFile" => {
+                self.load_subdir(name)?;
+                self.cwd.push(name.to_string());
+                let keys = self
+                    .cwd()
+                    .keys()
+                    .iter()
+                    .map(|k| format!("{};{}", k.name(), k.cycle()))
+                    .collect::<Vec<_>>();
+                for k in keys {
+                    self.delete_in_cwd(&k)?;
+                }
+                let (seek_keys, n_bytes_keys) = (self.cwd().seek_keys, self.cwd().n_bytes_keys);
+                if seek_keys > 0 {
+                    self.make_free(seek_keys, seek_keys + n_bytes_keys as i64 - 1)?;
+                }
+                self.cwd.pop();
+                self.cwd_mut().dirs.retain(|d| d.name() != name);
+            }
+            "TTree" => {
+                let reader = self.read_back()?;
+                // cycles of a tree written by AutoSave share baskets, the ones used by the other
+                // trees of the directory are kept
+                let others = self
+                    .cwd()
+                    .keys()
+                    .iter()
+                    .filter(|k| k.class_name() == "TTree" && k.seek_key() != key.seek_key())
+                    .map(|k| format!("{};{}", k.name(), k.cycle()))
+                    .collect::<Vec<_>>();
+                let mut used = BTreeSet::new();
+                for other in others {
+                    used.extend(self.tree_baskets(&other, &reader)?);
+                }
+                for (first, last) in self.tree_baskets(&namecycle, &reader)? {
+                    if !used.contains(&(first, last)) {
+                        self.make_free(first, last)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let seek = key.seek_key();
+        self.make_free(seek, seek + key.n_bytes() as i64 - 1)?;
+        self.cwd_mut()
+            .keys
+            .retain(|k| k.name() != name || k.cycle() != key.cycle());
+        Ok(())
+    }
+
+    /// Segments `(first, last)` of the baskets of the tree `namecycle` of the current directory.
+    fn tree_baskets(
+        &self,
+        namecycle: &str,
+        reader: &RootFileReader,
+    ) -> Result<BTreeSet<(i64, i64)>> {
+        let tree = self.cwd().get_tree(namecycle, reader, &self.sinfos)?;
+        Ok(tree
+            .branches_r()
+            .into_iter()
+            .flat_map(|b| <&TBranch>::from(b).basket_ranges())
+            .map(|b| (b.seek as i64, (b.seek + b.len as u64) as i64 - 1))
+            .collect())
+    }
+
+    /// Reader of the file being written, with the objects written so far.
+    fn read_back(&mut self) -> Result<RootFileReader> {
+        self.writer()?.flush()?;
+        match &self.inner {
+            RootFileInner::Update(r, _) => Ok(r.clone()),
+            RootFileInner::Writer(w) => RootFileReader::new(&w.path),
+            RootFileInner::Reader(r) => Ok(r.clone()),
+            RootFileInner::None => Err(Error::FileIsNotOpened),
+        }
+    }
+
+    fn new_subdir(&mut self, name: &str) -> Result<()> {
+        if let Some(key) = self.cwd().keys().iter().find(|k| k.name() == name) {
+            return Err(Error::KeyClassMismatch {
+                key: name.to_string(),
+                key_class: key.class_name().to_string(),
+                obj_class: "TDirectory".to_string(),
+            });
+        }
+
+        let obj_len = TDirectoryFile::record_size(self.version()) as i32;
+        let mut key = Key::new(
+            name.to_string(),
+            String::new(),
+            "TDirectory".to_string(),
+            obj_len,
+            self,
+        )?;
+        // the record of the directory is written when the file is closed
+        key.write_to_file(self.writer()?)?;
+        // as keys read from the file, see TDirectoryFile::read_keys
+        key.set_class("TDirectoryFile");
+
+        let mut dir = TDirectoryFile::new(name.to_string());
+        dir.seek_dir = key.seek_key();
+        dir.seek_parent = self.cwd().seek_dir;
+        dir.n_bytes_name = key.key_len();
+        trace!(";RootFile.new_subdir.seek_dir:{:?}", dir.seek_dir);
+
+        let cwd = self.cwd_mut();
+        cwd.keys.push(key);
+        cwd.dirs.push(dir);
+        Ok(())
+    }
+
+    /// Directory where objects are written.
+    pub(crate) fn cwd(&self) -> &TDirectoryFile {
+        let mut dir = &self.dir;
+        for name in self.cwd.iter() {
+            dir = dir.subdir(name).expect("current directory does not exist");
+        }
+        dir
+    }
+
+    fn cwd_mut(&mut self) -> &mut TDirectoryFile {
+        let mut dir = &mut self.dir;
+        for name in self.cwd.iter() {
+            dir = dir
+                .subdir_mut(name)
+                .expect("current directory does not exist");
+        }
+        dir
+    }
+
+    pub(crate) fn cwd_path(&self) -> &[String] {
+        &self.cwd
+    }
+
+    pub(crate) fn set_cwd(&mut self, cwd: Vec<String>) {
+        self.cwd = cwd;
+    }
+
     pub(crate) fn add_streamer_info(&mut self, si: StreamerInfo) {
         if self.sinfos.list().iter().any(|s| s.name() == si.name()) {
             return;
@@ -357,13 +938,19 @@ impl RootFile {
         self.write_streamer_info()?;
         self.write_free_segments()?;
         self.write_header()?;
+        self.writer()?.flush()?;
 
         Ok(())
     }
 
-    fn reader(&self) -> Result<&RootFileReader> {
+    /// Reader of the file. In update mode, the objects written so far are flushed first, so they
+    /// can be read back before [close](RootFile::close).
+    pub(crate) fn reader(&mut self) -> Result<&RootFileReader> {
+        if let RootFileInner::Update(_, w) = &mut self.inner {
+            w.flush()?;
+        }
         match &self.inner {
-            RootFileInner::Reader(r) => Ok(r),
+            RootFileInner::Reader(r) | RootFileInner::Update(r, _) => Ok(r),
             RootFileInner::Writer(_) => Err(Error::FileIsOpenedWriteOnly),
             _ => Err(Error::FileIsNotOpened),
         }
@@ -371,13 +958,13 @@ impl RootFile {
 
     pub(crate) fn writer(&mut self) -> Result<&mut RootFileWriter> {
         match &mut self.inner {
-            RootFileInner::Writer(w) => Ok(w),
+            RootFileInner::Writer(w) | RootFileInner::Update(_, w) => Ok(w),
             RootFileInner::Reader(_) => Err(Error::FileIsOpenedReadOnly),
             _ => Err(Error::FileIsNotOpened),
         }
     }
 
-    pub(crate) fn read_at(&self, start: u64, len: u64) -> Result<Vec<u8>> {
+    pub(crate) fn read_at(&mut self, start: u64, len: u64) -> Result<Vec<u8>> {
         let mut reader = self.reader()?.clone();
         reader.read_at(start, len)
     }
@@ -388,6 +975,28 @@ impl RootFile {
     }
 
     fn read_header(&mut self) -> Result<()> {
+        self.read_header_record()?;
+
+        let mut dir = TDirectoryFile::read_dir_info(self)?;
+
+        if self.header.seek_free > 0 {
+            self.read_free_segments()?;
+        }
+
+        if self.header.seek_info > 0 {
+            self.read_streamer_info()?;
+        }
+
+        dir.read_keys(self)?;
+
+        self.dir = dir;
+
+        // f.version %= 1000000
+
+        Ok(())
+    }
+
+    fn read_header_record(&mut self) -> Result<()> {
         trace!("start to read header");
         let buf = self.read_at(0, HEADER_LEN + HEADER_EXTRA_LEN)?;
         let mut r = RBuffer::new(&buf, 0);
@@ -396,7 +1005,9 @@ impl RootFile {
 
         trace!("magic = {:?}", magic);
 
-        assert_eq!(magic, ROOT_MAGIC.as_bytes());
+        if magic != ROOT_MAGIC.as_bytes() {
+            return Err(Error::NotARootFile);
+        }
 
         let version = r.read_i32()?;
         trace!("version = {}", version);
@@ -441,22 +1052,6 @@ impl RootFile {
 
         trace!("uuid = {}", self.header.uuid);
 
-        let mut dir = TDirectoryFile::read_dir_info(self)?;
-
-        if self.header.seek_free > 0 {
-            self.read_free_segments()?;
-        }
-
-        if self.header.seek_info > 0 {
-            self.read_streamer_info()?;
-        }
-
-        dir.read_keys(self)?;
-
-        self.dir = dir;
-
-        // f.version %= 1000000
-
         Ok(())
     }
 
@@ -467,13 +1062,67 @@ impl RootFile {
             panic!("self.spans.len()")
         }
         let blk = self.spans.vec().last_mut().unwrap();
-        if blk.last != kStartBigFile {
+        if blk.last < kStartBigFile {
             panic!("blk.last")
         }
+        // as ROOT, the last free segment grows by steps of 1GB once the file is bigger than
+        // kStartBigFile
+        while blk.last <= pos {
+            blk.last += 1_000_000_000;
+        }
         blk.first = pos;
         Ok(())
     }
 
+    /// Position where to write a record of `nbytes`: in the smallest free segment large
+    /// enough, see [FreeList::best_free], or at the end of the file.
+    pub(crate) fn allocate(&mut self, nbytes: i32) -> Result<i64> {
+        let nbytes = nbytes as i64;
+        let i = match self.spans.best_free(nbytes) {
+            None => {
+                let seek = self.end();
+                self.set_end(seek + nbytes)?;
+                return Ok(seek);
+            }
+            Some(i) => i,
+        };
+
+        let seg = &self.spans.vec()[i];
+        let (seek, left) = (seg.first, seg.last - seg.first - nbytes + 1);
+        trace!(";RootFile.allocate.seek:{:?}", seek);
+        trace!(";RootFile.allocate.left:{:?}", left);
+        if left == 0 {
+            self.spans.vec().remove(i);
+        } else {
+            self.spans.vec()[i].first = seek + nbytes;
+            self.write_gap(seek + nbytes, left)?;
+        }
+        Ok(seek)
+    }
+
+    /// Return the bytes from `first` to `last` (included) to the free segments.
+    pub(crate) fn make_free(&mut self, first: i64, last: i64) -> Result<()> {
+        trace!(";RootFile.make_free.first:{:?}", first);
+        trace!(";RootFile.make_free.last:{:?}", last);
+        let i = self.spans.add(first, last);
+        let seg = &self.spans.vec()[i];
+        let (first, size) = (seg.first, seg.last - seg.first + 1);
+        if i + 1 == self.spans.len() {
+            // merged with the trailing segment, the file gets shorter
+            self.header.end = first;
+            return Ok(());
+        }
+        self.write_gap(first, size)
+    }
+
+    /// As ROOT, a gap starts with its size negated, so that the records of the file can
+    /// be scanned.
+    fn write_gap(&mut self, seek: i64, size: i64) -> Result<()> {
+        let mut w = WBuffer::new(0);
+        w.write_i32(-(size.min(kStartBigFile) as i32))?;
+        self.write_at(w.p(), seek as u64)
+    }
+
     fn write_header(&mut self) -> Result<()> {
         self.header.n_free = self.spans.len() as i32;
 
@@ -559,8 +1208,13 @@ impl RootFile {
 
         while !rbuf.is_empty() {
             let span = rbuf.read_object_into::<FreeSegments>()?;
+            // the trailing segment is the last one, the record may be padded after it
+            let last = span.last > self.end();
 
             self.spans.append(span);
+            if last {
+                break;
+            }
         }
 
         Ok(())
@@ -619,6 +1273,11 @@ impl RootFile {
             self.sinfos.list().len()
         );
 
+        if self.header.seek_info > 0 {
+            let seek = self.header.seek_info;
+            self.make_free(seek, seek + self.header.n_bytes_info as i64 - 1)?;
+        }
+
         self.find_deep_streamer()?;
 
         trace!(
@@ -633,10 +1292,6 @@ impl RootFile {
             sinfos.push(si, addr_of!(*si) as usize);
         }
 
-        if self.header.seek_info != 0 {
-            unimplemented!("f.seek_info != 0")
-        }
-
         trace!(
             ";RootFile.write_streamer_info.sinfos.title:{}",
             sinfos.title()
@@ -671,6 +1326,7 @@ impl RootFile {
             sinfos.class().to_string(),
             1,
             buf.buffer(),
+            self.compression(),
             self,
         )?;
 
@@ -693,8 +1349,10 @@ impl RootFile {
 
     fn write_free_segments(&mut self) -> Result<()> {
         trace!(";RootFile.write_free_segments.call:{}", true);
-        if self.header.seek_free != 0 {
-            unimplemented!("self.header.seek_free != 0")
+
+        if self.header.seek_free > 0 {
+            let seek = self.header.seek_free;
+            self.make_free(seek, seek + self.header.n_bytes_free as i64 - 1)?;
         }
 
         let mut nbytes = 0;
@@ -703,30 +1361,38 @@ impl RootFile {
         }
         trace!(";RootFile.write_free_segments.nbytes:{}", nbytes);
 
-        let mut key = Key::new(
-            self.dir.dir().named().name.clone(),
-            self.dir.dir().named().title.clone(),
-            "TFile".to_string(),
-            nbytes,
-            self,
-        )?;
-
-        assert_ne!(key.seek_key(), 0);
-
-        if !self.is_big_file() && self.end() > kStartBigFile {
-            unimplemented!("!self.is_big_file() && self.end() > kStartBigFile")
-        }
+        let (mut key, buf) = loop {
+            let key = Key::new(
+                self.dir.dir().named().name.clone(),
+                self.dir.dir().named().title.clone(),
+                "TFile".to_string(),
+                nbytes,
+                self,
+            )?;
 
-        let nbytes = key.obj_len();
-        let mut buf = WBuffer::new(0);
+            assert_ne!(key.seek_key(), 0);
 
-        for span in self.spans.vec() {
-            span.marshal(&mut buf)?;
-        }
+            let mut buf = WBuffer::new(0);
+            for span in self.spans.vec() {
+                span.marshal(&mut buf)?;
+            }
 
-        if buf.pos() != nbytes.into() {
-            unimplemented!("buf.pos() != nbytes as usize")
-        }
+            // the key of the free segments may move the last segment above kStartBigFile, where
+            // it is written with 64 bits: the key is allocated again with the new size
+            let len = buf.pos() as i32;
+            if len > key.obj_len() {
+                trace!(";RootFile.write_free_segments.grow:{}", len);
+                let seek = key.seek_key();
+                self.make_free(seek, seek + key.n_bytes() as i64 - 1)?;
+                nbytes = len;
+                continue;
+            }
+            // or use a free segment entirely, as ROOT the record is padded
+            for _ in len..key.obj_len() {
+                buf.write_u8(0)?;
+            }
+            break (key, buf);
+        };
 
         self.header.n_bytes_free = key.n_bytes();
         self.header.seek_free = key.seek_key();
@@ -752,7 +1418,13 @@ impl RootFile {
             vers: i16,
         }
 
-        for (i, si) in self.sinfos.list().iter().enumerate() {
+        for (i, si) in self
+            .sinfos
+            .list()
+            .iter()
+            .enumerate()
+            .skip(self.n_sinfos_read)
+        {
             trace!(
                 ";Rootfile.find_deep_streamer.for_loop.{i}.si.name:{:?}",
                 si.name()
@@ -879,31 +1551,366 @@ impl RootFile {
         Ok(())
     }
 
-    fn get_object(&mut self, name: &str) -> Result<Box<dyn FactoryItemRead>> {
+    /// Tree at `path`, as `dir/sub/tree` for trees stored in subdirectories. The last cycle of
+    /// the tree is returned, older cycles are read with `tree;cycle`.
+    ///
+    /// Friends of the tree recorded by ROOT `TTree::AddFriend` are read, from this file or from
+    /// their own file, and added with [ReaderTree::add_friend]. Friend files are looked for at
+    /// their recorded path, then next to this file. Friends which can not be read are listed with
+    /// the error by [ReaderTree::unresolved_friends].
+    pub fn get_tree(&mut self, path: &str) -> Result<ReaderTree> {
+        self.get_tree_with_friends(path, &mut Vec::new())
+    }
+
+    /// Tree at `path` with its friends, as [get_tree](RootFile::get_tree). `seen` holds the
+    /// (file, path) of the trees being read, friends of friends pointing back to one of them are
+    /// not read again.
+    fn get_tree_with_friends(
+        &mut self,
+        path: &str,
+        seen: &mut Vec<(PathBuf, String)>,
+    ) -> Result<ReaderTree> {
+        let reader = self.reader()?.clone();
+        let mut tree = self.dir.get_tree(path, &reader, &self.sinfos)?;
+        seen.push((canonical_path(reader.name()), path.to_string()));
+
+        let elements = tree.friend_elements().to_vec();
+        self.add_friends(&mut tree, &elements, seen);
+        Ok(tree)
+    }
+
+    /// Read the friends `elements` of `tree` and add them to it. Friends which can not be read are
+    /// recorded with the error in [ReaderTree::unresolved_friends].
+    fn add_friends(
+        &mut self,
+        tree: &mut ReaderTree,
+        elements: &[FriendElement],
+        seen: &mut Vec<(PathBuf, String)>,
+    ) {
+        for element in elements {
+            trace!(";RootFile.get_tree.friend:{:?}", element);
+            let friend = match self.friend_file(element.file_name()) {
+                Ok(None) => self.get_friend(element.tree_name(), seen),
+                Ok(Some(mut file)) => file.get_friend(element.tree_name(), seen),
+                Err(e) => Err(e),
+            };
+            match friend {
+                Ok(Some(friend)) => tree.add_friend(friend, Some(element.alias())),
+                Ok(None) => {}
+                Err(e) => {
+                    trace!(";RootFile.get_tree.friend.err:{:?}", e);
+                    tree.add_unresolved_friend(element.clone(), e.to_string());
+                }
+            }
+        }
+    }
+
+    /// Friend tree at `path`, `None` if it is being read.
+    fn get_friend(
+        &mut self,
+        path: &str,
+        seen: &mut Vec<(PathBuf, String)>,
+    ) -> Result<Option<ReaderTree>> {
+        let file = canonical_path(self.reader()?.name());
+        if seen.iter().any(|(f, p)| *f == file && p == path) {
+            return Ok(None);
+        }
+        self.get_tree_with_friends(path, seen).map(Some)
+    }
+
+    /// File `file_name` of a friend, as ROOT `TFriendElement::GetFile`, `None` for this file.
+    fn friend_file(&mut self, file_name: &str) -> Result<Option<RootFile>> {
+        let this = canonical_path(self.reader()?.name());
+        let mut path = PathBuf::from(file_name);
+        if file_name.is_empty() || canonical_path(file_name) == this {
+            return Ok(None);
+        }
+        if !path.exists() {
+            // files moved together
+            if let (Some(dir), Some(name)) = (this.parent(), path.file_name()) {
+                path = dir.join(name);
+            }
+            if canonical_path(&path) == this {
+                return Ok(None);
+            }
+        }
+        RootFile::open(path).map(Some)
+    }
+
+    /// Object at `path`, as `dir/sub/name` or `name;cycle`, read as `T`. `T` is the type
+    /// registered for the class of the key, such as [TObjString](crate::rbase::TObjString),
+    /// [Named](crate::rbase::Named), [ReaderList](crate::rcont::list::ReaderList),
+    /// [TMap](crate::rcont::TMap) or [ReaderObjArray](crate::rcont::objarray::ReaderObjArray).
+    /// Reading a key of another class returns a `KeyClassMismatch` error. Trees are read with
+    /// [get_tree](RootFile::get_tree).
+    ///
+    /// ```
+    /// use oxyroot::rbase::TObjString;
+    /// use oxyroot::RootFile;
+    /// {
+    ///     let mut f = RootFile::create("/tmp/get.root").expect("Can not create file");
+    ///     f.put("provenance", &TObjString::new("skimmed with cuts v3"))
+    ///         .expect("Can not write string");
+    ///     f.close().expect("Can not close file");
+    /// }
+    /// let mut f = RootFile::open("/tmp/get.root").expect("Can not open file");
+    /// let note = f.get::<TObjString>("provenance").expect("Can not read string");
+    /// assert_eq!(note.to_string(), "skimmed with cuts v3");
+    /// ```
+    pub fn get<T: FactoryItemRead>(&mut self, path: &str) -> Result<T> {
         let mut reader = self.reader()?.clone();
-        self.dir.get_object(name, &mut reader, Some(&self.sinfos))
+        self.dir.get(path, &mut reader, Some(&self.sinfos))
+    }
 
-        // let a = &(&*self).dir;
-        // let b = self.reader()?;
-        //
-        // a.get_object(name, b, Some(&self.sinfos))
+    /// Directory at `path`, as `dir/sub`. An empty path gives the top directory of the file.
+    pub fn get_directory(&mut self, path: &str) -> Result<Directory> {
+        let mut reader = self.reader()?.clone();
+        let dir = self
+            .dir
+            .get_directory(path, &mut reader, Some(&self.sinfos))?;
+        let path = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(Directory::new(dir, path, reader, self.sinfos.clone()))
+    }
+
+    /// Check the records of the file without panicking on corrupted files: the header, the keys
+    /// of all directories with their payloads, the baskets of all trees and the free segments.
+    /// Problems found are listed in the returned [VerifyReport], which is empty when the file is
+    /// sound. Files which can not be opened, as truncated files, are checked with
+    /// [verify_path](RootFile::verify_path).
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let mut f = RootFile::open("examples/from_uproot/data/HZZ.root").expect("Can not open file");
+    /// let report = f.verify().expect("Can not verify file");
+    /// assert!(report.is_ok(), "{report}");
+    /// ```
+    pub fn verify(&mut self) -> Result<VerifyReport> {
+        let reader = self.reader()?.clone();
+        let mut v = Verifier::new(reader, self.sinfos.clone(), self.end());
+        let begin = self.begin();
+        let n_bytes_begin =
+            self.n_bytes_name() as i64 + TDirectoryFile::record_size(self.version());
+        v.top_directory(begin, n_bytes_begin);
+        v.key_record(
+            "<streamer info>",
+            self.header.seek_info,
+            self.header.n_bytes_info as i64,
+        );
+        if self.header.seek_free > 0 {
+            v.key_record(
+                "<free segments>",
+                self.header.seek_free,
+                self.header.n_bytes_free as i64,
+            );
+        }
+        v.directory("", &self.dir);
+        v.free_segments(self.spans.vec());
+        Ok(v.finish())
+    }
 
-        // Ok(obj)
+    /// Check the file at `path` as [verify](RootFile::verify), also when it can not be opened with
+    /// [open](RootFile::open), as files left behind by crashed jobs. Only the header of the file
+    /// has to be readable, the records which prevent opening the file are reported as problems.
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let data = std::fs::read("examples/from_uproot/data/HZZ.root").expect("Can not read file");
+    /// std::fs::write("/tmp/truncated.root", &data[..data.len() / 2]).expect("Can not write file");
+    /// let report = RootFile::verify_path("/tmp/truncated.root").expect("Can not verify file");
+    /// assert!(!report.is_ok());
+    /// ```
+    pub fn verify_path<P>(path: P) -> Result<VerifyReport>
+    where
+        P: AsRef<Path>,
+    {
+        let reader = RootFileReader::new(path)?;
+        let name = reader.to_string();
+        let mut f = RootFile {
+            inner: RootFileInner::Reader(reader),
+            ..Default::default()
+        };
+        f.read_header_record()?;
+
+        // as read_header, records which can not be read are reported by verify
+        match TDirectoryFile::read_dir_info(&mut f) {
+            Ok(mut dir) => {
+                if let Err(e) = dir.read_keys(&mut f) {
+                    debug!("can not read keys of {name}: {e:?}");
+                    dir.keys.clear();
+                }
+                f.dir = dir;
+            }
+            Err(e) => debug!("can not read top directory of {name}: {e:?}"),
+        }
+        if f.header.seek_free > 0 {
+            if let Err(e) = f.read_free_segments() {
+                debug!("can not read free segments of {name}: {e:?}");
+            }
+        }
+        if f.header.seek_info > 0 {
+            if let Err(e) = f.read_streamer_info() {
+                debug!("can not read streamer info of {name}: {e:?}");
+            }
+        }
+        f.verify()
     }
 
-    pub fn get_tree(&mut self, name: &str) -> Result<ReaderTree> {
-        let objet = self.get_object(name)?;
-        let mut objet: ReaderTree = *objet.downcast::<ReaderTree>().expect("");
+    /// Open a file which was not closed properly, as the output of a crashed job, as ROOT
+    /// `TFile::Recover`.
+    ///
+    /// Files which can be opened with [open](RootFile::open) are returned as is. Otherwise the
+    /// records are read one after the other from the start of the file, until one can not be read
+    /// entirely, and the keys of the top directory are rebuilt from the keys found, with the
+    /// streamer infos when they were written. Objects in subdirectories are not recovered. Trees
+    /// whose key was not written yet, or baskets written after their key, are recovered with
+    /// [recover_tree](RootFile::recover_tree) and [recover_tree_from](RootFile::recover_tree_from).
+    pub fn open_recover<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let reader = RootFileReader::new(path)?;
+        match Self::open_reader(reader.clone()) {
+            Ok(f) => return Ok(f),
+            Err(e) => debug!("can not open {reader}, try to recover it: {e:?}"),
+        }
 
-        objet.set_reader(Some(self.reader()?.clone()));
-        objet.set_streamer_info(self.sinfos.clone());
-        Ok(objet)
+        let mut f = RootFile {
+            inner: RootFileInner::Reader(reader),
+            ..Default::default()
+        };
+        f.read_header_record()?;
+        let begin = f.begin();
+        let scan = scan_records(&mut f.reader()?.clone(), begin);
+        f.header.end = scan.end;
+        f.header.seek_free = 0;
+        f.header.n_bytes_free = 0;
+        f.header.seek_info = 0;
+        f.header.n_bytes_info = 0;
+
+        let mut dir = TDirectoryFile::read_dir_info(&mut f)?;
+        let mut reader = f.reader()?.clone();
+        for mut key in scan.keys.into_iter() {
+            if key.seek_pdir() != dir.seek_dir || key.class() == "TFile" {
+                continue;
+            }
+            if key.name() == "StreamerInfo" && key.class() == "TList" {
+                f.header.seek_info = key.seek_key();
+                f.header.n_bytes_info = key.n_bytes();
+                continue;
+            }
+            if key.class() == "TDirectory" || key.class() == "TDirectoryFile" {
+                // lists of keys are also written with the TDirectory class, directories are told
+                // apart by their record
+                let is_dir = key
+                    .bytes(&mut reader, None)
+                    .ok()
+                    .and_then(|buf| {
+                        RBuffer::new(&buf, 0)
+                            .read_object_into::<TDirectoryFile>()
+                            .ok()
+                    })
+                    .is_some_and(|d| d.seek_dir == key.seek_key());
+                if !is_dir {
+                    continue;
+                }
+                key.set_class("TDirectoryFile");
+            }
+            trace!(";RootFile.open_recover.key:{:?}", key.name());
+            dir.keys.push(key);
+        }
+
+        if f.header.seek_info > 0 {
+            if let Err(e) = f.read_streamer_info() {
+                debug!("can not read streamer info of {reader}: {e:?}");
+            }
+        }
+        f.dir = dir;
+        Ok(f)
+    }
+
+    /// Tree at `path`, as [get_tree](RootFile::get_tree), with the baskets written after its key
+    /// found by reading the records of the file. Baskets are added to a branch when they follow
+    /// the last basket of the branch, then the tree has the entries of its shortest branch.
+    pub fn recover_tree(&mut self, path: &str) -> Result<ReaderTree> {
+        let mut tree = self.get_tree(path)?;
+        let dir = path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        let mut reader = self.reader()?.clone();
+        let seek_dir = self
+            .dir
+            .get_directory(dir, &mut reader, Some(&self.sinfos))?
+            .seek_dir;
+
+        let baskets = scan_records(&mut reader, self.begin())
+            .baskets
+            .into_iter()
+            .filter(|b| b.seek_pdir == seek_dir)
+            .collect::<Vec<_>>();
+        tree.append_baskets(&baskets);
+        Ok(tree)
+    }
+
+    /// Tree whose key was not written, as trees of crashed jobs, from the baskets found by
+    /// reading the records of the file. `layout` is the same tree read from another file, as a
+    /// file written by the same job which was properly closed: its branches are kept, its
+    /// baskets are replaced by the ones of this file. The tree has the entries of its shortest
+    /// branch.
+    ///
+    /// ```
+    /// use oxyroot::{RootFile, WriterTree};
+    /// let path = "/tmp/recover_tree_from.root";
+    /// {
+    ///     let mut f = RootFile::create(path).expect("Can not create file");
+    ///     let mut tree = WriterTree::new("mytree");
+    ///     tree.new_branch("n", 0..1_000_000);
+    ///     tree.write(&mut f).expect("Can not write tree");
+    ///     f.close().expect("Can not close file");
+    /// }
+    /// let layout = RootFile::open(path).unwrap().get_tree("mytree").unwrap();
+    ///
+    /// // a job writing the same tree crashed, its file ends in the middle of the baskets
+    /// let data = std::fs::read(path).unwrap();
+    /// std::fs::write(path, &data[..data.len() / 2]).unwrap();
+    /// assert!(RootFile::open(path).is_err());
+    ///
+    /// let mut f = RootFile::open_recover(path).expect("Can not recover file");
+    /// let tree = f.recover_tree_from(layout).expect("Can not recover tree");
+    /// assert!(tree.entries() > 0);
+    /// let n = tree.branch("n").unwrap().as_iter::<i32>().unwrap();
+    /// assert!(n.enumerate().all(|(i, n)| n == i as i32));
+    /// ```
+    pub fn recover_tree_from(&mut self, layout: ReaderTree) -> Result<ReaderTree> {
+        let mut tree = layout;
+        let mut reader = self.reader()?.clone();
+        let seek_dir = self.dir.seek_dir;
+        let baskets = scan_records(&mut reader, self.begin())
+            .baskets
+            .into_iter()
+            .filter(|b| b.seek_pdir == seek_dir)
+            .collect::<Vec<_>>();
+
+        tree.clear_baskets();
+        tree.append_baskets(&baskets);
+        tree.set_reader(Some(reader));
+        if !self.sinfos.list().is_empty() {
+            tree.set_streamer_info(self.sinfos.clone());
+        }
+        Ok(tree)
     }
 
     pub fn keys_name(&self) -> impl Iterator<Item = &str> {
         self.dir.keys().iter().map(|e| e.name())
     }
 
+    /// Cycles of the key at `path`, as `dir/sub/name`, in increasing order.
+    pub fn cycles(&mut self, path: &str) -> Result<Vec<i32>> {
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        Ok(self.get_directory(dir)?.cycles(name))
+    }
+
     pub fn keys(&self) -> Vec<Key> {
         self.dir.keys().clone()
     }
@@ -921,6 +1928,7 @@ impl RootFile {
     fn title(&self) -> &str {
         self.dir().dir().named().title()
     }
+    /// Compression settings of the file, encoded as in ROOT (`algorithm * 100 + level`).
     pub fn compression(&self) -> i32 {
         self.header.compression()
     }
@@ -928,27 +1936,27 @@ impl RootFile {
 
 #[derive(Default, Clone, Debug)]
 pub(crate) struct RootFileStreamerInfoContext {
-    list: Rc<Vec<StreamerInfo>>,
+    list: Arc<Vec<StreamerInfo>>,
 }
 
 impl RootFileStreamerInfoContext {
     pub(crate) fn new() -> Self {
         Self {
-            list: Rc::new(Vec::new()),
+            list: Arc::new(Vec::new()),
         }
     }
 
     pub(crate) fn push(&mut self, info: StreamerInfo) {
-        let v = Rc::get_mut(&mut self.list).expect("Do not panic ! ");
+        let v = Arc::get_mut(&mut self.list).expect("Do not panic ! ");
         v.push(info);
     }
 
     fn extend(&mut self, infos: Vec<StreamerInfo>) {
-        let v = Rc::get_mut(&mut self.list).expect("Do not panic ! ");
+        let v = Arc::get_mut(&mut self.list).expect("Do not panic ! ");
         v.extend(infos);
     }
 
-    pub(crate) fn list(&self) -> &Rc<Vec<StreamerInfo>> {
+    pub(crate) fn list(&self) -> &Arc<Vec<StreamerInfo>> {
         &self.list
     }
 
@@ -979,3 +1987,300 @@ impl StreamerInfoContext for RootFileStreamerInfoContext {
         None
     }
 }
+
+/// `path` with symbolic links and relative components resolved, when it exists.
+fn canonical_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    std::fs::canonicalize(path.as_ref()).unwrap_or_else(|_| path.as_ref().to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rbase::{Named as TNamed, TObjString};
+    use crate::rcont::list::{ReaderList, WriterList};
+    use crate::rcont::tmap::{Key as MapKey, TMap, WriterMap};
+    use crate::riofs::Problem;
+    use crate::WriterTree;
+
+    #[test]
+    fn put_objects() -> anyhow::Result<()> {
+        let dir = "/tmp/rust/put";
+        std::fs::create_dir_all(dir)?;
+        let path = format!("{dir}/objects.root");
+
+        {
+            let mut f = RootFile::create(&path)?;
+            f.put("note", &TObjString::new("first"))?;
+            f.put("note", &TObjString::new("second"))?;
+            f.put("", &TNamed::new("config", "pt > 20"))?;
+
+            let (a, b) = (TObjString::new("a"), TNamed::new("b", "title of b"));
+            let mut list = WriterList::new();
+            list.add(&a);
+            list.add(&b);
+            f.put("list", &list)?;
+
+            let (key, value) = (TObjString::new("key"), TObjString::new("value"));
+            let mut map = WriterMap::new();
+            map.insert(&key, &value);
+            f.put("map", &map)?;
+
+            let mut sub = f.mkdir("dir")?;
+            sub.put("note", &TObjString::new("in dir"))?;
+            drop(sub);
+
+            assert!(matches!(
+                f.put("a/b", &TObjString::new("")),
+                Err(Error::NameContainsSlash(_))
+            ));
+            assert!(matches!(
+                f.put("note", &TNamed::new("note", "")),
+                Err(Error::KeyClassMismatch { .. })
+            ));
+            f.close()?;
+        }
+
+        let mut f = RootFile::open(&path)?;
+        assert!(matches!(
+            f.get::<TNamed>("note"),
+            Err(Error::KeyClassMismatch { key_class, obj_class, .. })
+                if key_class == "TObjString" && obj_class == "TNamed"
+        ));
+        assert!(matches!(
+            f.get_tree("config"),
+            Err(Error::KeyClassMismatch { key_class, obj_class, .. })
+                if key_class == "TNamed" && obj_class == "TTree"
+        ));
+        assert_eq!(f.get::<TNamed>("config")?.title(), "pt > 20");
+
+        let mut reader = f.reader()?.clone();
+        let mut get = |path: &str| f.dir.get_object(path, &mut reader, Some(&f.sinfos));
+        assert_eq!(f.dir.cycles("note"), vec![1, 2]);
+
+        let note = get("note")?.downcast::<TObjString>().unwrap();
+        assert_eq!(note.to_string(), "second");
+        let note = get("note;1")?.downcast::<TObjString>().unwrap();
+        assert_eq!(note.to_string(), "first");
+        let note = get("dir/note")?.downcast::<TObjString>().unwrap();
+        assert_eq!(note.to_string(), "in dir");
+
+        let config = get("config")?.downcast::<TNamed>().unwrap();
+        assert_eq!(config.title(), "pt > 20");
+
+        let list = get("list")?.downcast::<ReaderList>().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.at::<TObjString>(0).to_string(), "a");
+        assert_eq!(list.at::<TNamed>(1).title(), "title of b");
+
+        let map = get("map")?.downcast::<TMap>().unwrap();
+        let value = map.get::<TObjString>(&MapKey::String("key".to_string()));
+        assert_eq!(value.unwrap().to_string(), "value");
+        Ok(())
+    }
+
+    #[test]
+    fn verify_corrupted_files() -> anyhow::Result<()> {
+        let dir = "/tmp/rust/verify";
+        std::fs::create_dir_all(dir)?;
+        let path = format!("{dir}/sound.root");
+        let n = 100_000;
+        {
+            let mut f = RootFile::create(&path)?;
+            let mut tree = WriterTree::new("t");
+            tree.new_branch("n", 0..n);
+            tree.new_branch("s", (0..n).map(|i| format!("evt-{i}")));
+            tree.write(&mut f)?;
+            f.close()?;
+        }
+
+        let mut f = RootFile::open(&path)?;
+        let report = f.verify()?;
+        assert!(report.is_ok(), "{report}");
+        assert_eq!(report.n_keys, 1);
+        let seeks = f
+            .get_tree("t")?
+            .branch("n")
+            .unwrap()
+            .tbranch()
+            .basket_seek
+            .clone();
+        let n_baskets = report.n_baskets;
+        assert!(n_baskets > 2);
+
+        let data = std::fs::read(&path)?;
+
+        // header of the second basket of n, payload of the third one
+        let mut corrupted = data.clone();
+        corrupted[seeks[1] as usize..seeks[1] as usize + 16].fill(0);
+        let (seek, key_len) = (seeks[2] as usize, 50);
+        corrupted[seek + key_len..seek + key_len + 32].fill(0xff);
+        let report = RootFile::from_bytes(corrupted)?.verify()?;
+        assert_eq!(report.n_baskets, n_baskets);
+        assert!(
+            matches!(
+                &report.problems[..],
+                [Problem::BadKey { path: a, .. }, Problem::BadPayload { path: b, .. }]
+                    if a == "t;1/n[1]" && b == "t;1/n[2]"
+            ),
+            "{report}"
+        );
+
+        // the file is cut after it has been opened, as if it was read while being written: the
+        // records written at the end, with the tree, are missing
+        let mut f = RootFile::from_bytes(data.clone())?;
+        let cut = seeks[2] as usize;
+        f.inner = RootFileInner::Reader(RootFileReader::from_reader_at(
+            "<truncated>".to_string(),
+            MemReader(data[..cut].to_vec()),
+        ));
+        let report = f.verify()?;
+        assert_eq!(report.problems[0], Problem::Truncated { end: f.end() });
+        let out_of_file = report
+            .problems
+            .iter()
+            .filter_map(|p| match p {
+                Problem::OutOfFile { path, .. } => Some(path.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            out_of_file,
+            ["<streamer info>", "<free segments>", "<keys>", "t;1"],
+            "{report}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn friend_files() -> anyhow::Result<()> {
+        let dir = "/tmp/rust/friend_files";
+        std::fs::create_dir_all(dir)?;
+        for name in ["main.root", "calib.root"] {
+            let mut f = RootFile::create(format!("{dir}/{name}"))?;
+            let mut tree = WriterTree::new("tree");
+            tree.new_branch("i", 0..3);
+            tree.write(&mut f)?;
+            f.close()?;
+        }
+
+        let mut f = RootFile::open(format!("{dir}/main.root"))?;
+        assert!(f.friend_file("")?.is_none());
+        assert!(f.friend_file(&format!("{dir}/main.root"))?.is_none());
+        assert!(f
+            .friend_file(&format!("{dir}/../friend_files/main.root"))?
+            .is_none());
+        // recorded where the files were written, before they were moved
+        assert!(f.friend_file("/elsewhere/main.root")?.is_none());
+        let mut calib = f.friend_file("/elsewhere/calib.root")?.unwrap();
+        assert!(calib.reader()?.name().ends_with("calib.root"));
+        assert!(f.friend_file("/elsewhere/missing.root").is_err());
+
+        let mut tree = f.get_tree("tree")?;
+        assert_eq!(tree.unresolved_friends().count(), 0);
+        let elements = [
+            FriendElement::new("calib", "tree", "/elsewhere/calib.root"),
+            FriendElement::new("missing", "tree", "/elsewhere/missing.root"),
+            FriendElement::new("other", "other", ""),
+        ];
+        f.add_friends(&mut tree, &elements, &mut Vec::new());
+        assert!(tree.friend("calib").is_some());
+        let unresolved = tree
+            .unresolved_friends()
+            .map(|(element, _)| element.alias())
+            .collect::<Vec<_>>();
+        assert_eq!(unresolved, ["missing", "other"]);
+        Ok(())
+    }
+
+    #[test]
+    fn delete_autosave_cycle() -> anyhow::Result<()> {
+        let dir = "/tmp/rust/delete";
+        std::fs::create_dir_all(dir)?;
+        let path = format!("{dir}/autosave.root");
+        let n = 10_000;
+
+        {
+            let mut f = RootFile::create(&path)?;
+            let mut tree = WriterTree::new("events");
+            tree.new_branch("i", 0..n);
+            tree.write(&mut f)?;
+            // a second cycle of the tree with the same baskets, as written by AutoSave
+            let key = f.cwd().keys()[0].clone();
+            let buf = key.bytes(&mut f.read_back()?, None)?;
+            let compression = f.compression();
+            let cycle = Key::new_from_buffer(
+                key.name().to_string(),
+                key.title().to_string(),
+                key.class_name().to_string(),
+                2,
+                buf,
+                compression,
+                &mut f,
+            )?;
+            cycle.write_to_file(f.writer()?)?;
+            f.cwd_mut().keys.push(cycle);
+            f.close()?;
+        }
+        {
+            let mut f = RootFile::open_update(&path)?;
+            f.delete("events;1")?;
+            let mut tree = WriterTree::new("other");
+            tree.new_branch("v", (0..n).map(|i| i as f64));
+            tree.write(&mut f)?;
+            f.close()?;
+        }
+
+        let mut f = RootFile::open(&path)?;
+        let tree = f.get_tree("events")?;
+        assert!(tree.branch("i").unwrap().as_iter::<i32>()?.eq(0..n));
+        let tree = f.get_tree("other")?;
+        assert!(tree
+            .branch("v")
+            .unwrap()
+            .as_iter::<f64>()?
+            .eq((0..n).map(|i| i as f64)));
+        assert!(f.verify()?.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn write_above_start_big_file() -> anyhow::Result<()> {
+        let dir = "/tmp/rust/big_file";
+        std::fs::create_dir_all(dir)?;
+        let path = format!("{dir}/sparse.root");
+        let n = 100_000;
+
+        // leave a hole, the file is sparse on disk: objects are written across kStartBigFile, or
+        // after 4GB
+        for start in [kStartBigFile - 100_000, 5_000_000_000] {
+            {
+                let mut f = RootFile::create(&path)?;
+                f.set_end(start)?;
+                let mut tree = WriterTree::new("tree");
+                tree.new_branch("i", 0..n);
+                tree.write(&mut f)?;
+                let mut sub = f.mkdir("dir")?;
+                let mut tree = WriterTree::new("tree");
+                tree.new_branch("v", (0..n).map(|i| vec![i as f64; (i % 5) as usize]));
+                tree.write(&mut sub)?;
+                drop(sub);
+                f.close()?;
+            }
+
+            let mut f = RootFile::open(&path)?;
+            assert!(f.is_big_file());
+            assert!(f.header.seek_free > start);
+            assert!(f.header.seek_info > start);
+            let tree = f.get_tree("tree")?;
+            assert!(tree.branch("i").unwrap().as_iter::<i32>()?.eq(0..n));
+            let tree = f.get_tree("dir/tree")?;
+            let v = tree.branch("v").unwrap().as_iter::<Vec<f64>>()?;
+            assert!(v.eq((0..n).map(|i| vec![i as f64; (i % 5) as usize])));
+            let report = f.verify()?;
+            assert!(report.is_ok(), "{report}");
+        }
+        // several GB large, even if sparse
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
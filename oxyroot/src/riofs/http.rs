@@ -0,0 +1,170 @@
+use crate::riofs::reader::ReaderAt;
+use log::trace;
+use std::collections::{HashMap, VecDeque};
+use std::io::{Error, ErrorKind, Read};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// size of the blocks fetched from the server
+const BLOCK_SIZE: u64 = 256 * 1024;
+// number of blocks kept in memory, ie 64MB
+const CACHE_BLOCKS: usize = 256;
+
+pub(crate) fn is_http_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// Blocks of the remote file already fetched, oldest blocks are dropped first.
+#[derive(Default)]
+struct BlockCache {
+    blocks: HashMap<u64, Arc<Vec<u8>>>,
+    order: VecDeque<u64>,
+}
+
+impl BlockCache {
+    fn get(&self, n: u64) -> Option<Arc<Vec<u8>>> {
+        self.blocks.get(&n).cloned()
+    }
+
+    fn insert(&mut self, n: u64, block: Vec<u8>) {
+        if self.blocks.insert(n, Arc::new(block)).is_none() {
+            self.order.push_back(n);
+        }
+        while self.order.len() > CACHE_BLOCKS {
+            let old = self.order.pop_front().unwrap();
+            self.blocks.remove(&old);
+        }
+    }
+}
+
+/// Read a remote file with HTTP `Range` requests, block by block.
+pub(crate) struct HttpReader {
+    url: String,
+    agent: ureq::Agent,
+    size: u64,
+    cache: Mutex<BlockCache>,
+}
+
+impl HttpReader {
+    pub(crate) fn new(url: &str, timeout: Duration) -> std::io::Result<Self> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(timeout)
+            .timeout_read(timeout)
+            .timeout_write(timeout)
+            .build();
+        let mut reader = Self {
+            url: url.to_string(),
+            agent,
+            size: 0,
+            cache: Mutex::new(BlockCache::default()),
+        };
+
+        // the first request fetches the header of the file and gives its size
+        let (data, size) = reader.fetch(0, BLOCK_SIZE)?;
+        reader.size = size;
+        reader.cache.lock().unwrap().insert(0, data);
+        trace!(";HttpReader.new.{url}.size:{:?}", size);
+        Ok(reader)
+    }
+
+    /// Fetch bytes `start..end` and return them with the total size of the remote file.
+    fn fetch(&self, start: u64, end: u64) -> std::io::Result<(Vec<u8>, u64)> {
+        trace!(";HttpReader.fetch.{}.range:{:?}", self.url, start..end);
+        let resp = self
+            .agent
+            .get(&self.url)
+            .set("Range", &format!("bytes={}-{}", start, end - 1))
+            .call()
+            .map_err(Error::other)?;
+
+        if resp.status() != 206 {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "{}: server does not support range requests (status {})",
+                    self.url,
+                    resp.status()
+                ),
+            ));
+        }
+
+        // Content-Range: bytes start-end/size
+        let size = resp
+            .header("Content-Range")
+            .and_then(|r| r.rsplit('/').next())
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}: missing or invalid Content-Range header", self.url),
+                )
+            })?;
+
+        let mut data = Vec::with_capacity((end - start) as usize);
+        resp.into_reader().read_to_end(&mut data)?;
+        Ok((data, size))
+    }
+
+    /// Return block `n`, blocks from `n` up to `last` missing in cache are fetched in one request.
+    /// The cache is not locked during the request, threads reading other blocks are not blocked.
+    fn block(&self, n: u64, last: u64) -> std::io::Result<Arc<Vec<u8>>> {
+        let mut end = n + 1;
+        {
+            let cache = self.cache.lock().unwrap();
+            if let Some(block) = cache.get(n) {
+                return Ok(block);
+            }
+            while end <= last && end - n < CACHE_BLOCKS as u64 && cache.get(end).is_none() {
+                end += 1;
+            }
+        }
+
+        let start = n * BLOCK_SIZE;
+        let (data, _) = self.fetch(start, (end * BLOCK_SIZE).min(self.size))?;
+        if data.len() as u64 != (end * BLOCK_SIZE).min(self.size) - start {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("{}: short read at {start}", self.url),
+            ));
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        for (i, chunk) in data.chunks(BLOCK_SIZE as usize).enumerate() {
+            cache.insert(n + i as u64, chunk.to_vec());
+        }
+        Ok(cache.get(n).unwrap())
+    }
+}
+
+impl ReaderAt for HttpReader {
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        let end = start + buf.len() as u64;
+        if end > self.size {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{}: can not read {} bytes at {start}, file has {} bytes",
+                    self.url,
+                    buf.len(),
+                    self.size
+                ),
+            ));
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let last = (end - 1) / BLOCK_SIZE;
+        let mut pos = start;
+        while pos < end {
+            let n = pos / BLOCK_SIZE;
+            let block = self.block(n, last)?;
+            let beg = (pos - n * BLOCK_SIZE) as usize;
+            let len = (block.len() - beg).min((end - pos) as usize);
+            let off = (pos - start) as usize;
+            buf[off..off + len].copy_from_slice(&block[beg..beg + len]);
+            pos += len as u64;
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,13 @@
 use crate::rbytes::rbuffer::RBuffer;
 use crate::rbytes::wbuffer::WBuffer;
-use crate::rbytes::{Marshaler, StreamerInfoContext, Unmarshaler};
+use crate::rbytes::{Marshaler, RVersioner, StreamerInfoContext, Unmarshaler};
 use crate::riofs::consts::kStartBigFile;
 use crate::riofs::dir::TDirectoryFile;
 use crate::riofs::file::{RootFileReader, RootFileWriter};
-use crate::riofs::utils::datetime_to_u32;
+use crate::riofs::utils::{datetime_to_u32, u32_to_datetime};
 use crate::riofs::{utils, Result};
 use crate::root::traits::{datime_sizeof, tstring_sizeof, Named};
 use crate::root::{objects, traits};
-use crate::rtypes::factory::FactoryItemWrite;
 use crate::rtypes::FactoryItemRead;
 use crate::{rcompress, riofs, rvers};
 use crate::{rtypes, RootFile};
@@ -95,7 +94,7 @@ impl Unmarshaler for Key {
         let rvers = r.read_i16()?;
         trace!(";KEY.unmarshal.a{_beg}.rvers: {}", rvers);
         let obj_len = r.read_i32()?;
-        let datetime = DateTime::from_timestamp(r.read_u32()? as i64, 0).unwrap();
+        let datetime = u32_to_datetime(r.read_u32()?);
         let key_len = r.read_i16()? as i32;
         let cycle = r.read_i16()?;
 
@@ -167,7 +166,7 @@ impl Marshaler for Key {
         trace!(";KEY.marshal.{beg}.cycle:{:?}", self.cycle);
         w.write_i16(self.cycle)?;
 
-        if self.rvers > 1000 {
+        if rvers > 1000 {
             w.write_i64(self.seek_key)?;
             w.write_i64(self.seek_pdir)?;
         } else {
@@ -178,7 +177,12 @@ impl Marshaler for Key {
         trace!(";KEY.marshal.{beg}.seek_key:{:?}", self.seek_key);
         trace!(";KEY.marshal.{beg}.seek_pdir:{:?}", self.seek_pdir);
         trace!(";KEY.marshal.{beg}.class:{:?}", self.class);
-        w.write_string(&self.class)?;
+        // as ROOT, directories are recorded as "TDirectory", see TDirectoryFile::read_keys
+        if self.class == "TDirectoryFile" {
+            w.write_string("TDirectory")?;
+        } else {
+            w.write_string(&self.class)?;
+        }
         trace!(";KEY.marshal.{beg}.name:{:?}", self.name);
         w.write_string(&self.name)?;
         trace!(";KEY.marshal.{beg}.title:{:?}", self.title);
@@ -217,8 +221,9 @@ impl Key {
         obj_len: i32,
         f: &mut RootFile,
     ) -> Result<Self> {
+        let big = f.is_big_file();
         let mut key = Key {
-            key_len: key_len_for(&name, &title, &class, f),
+            key_len: key_len_for(&name, &title, &class, big),
             name,
             title,
             class,
@@ -227,16 +232,14 @@ impl Key {
             ..Default::default()
         };
         key.n_bytes = key.obj_len + key.key_len;
-        let eof = f.end();
         if obj_len > 0 {
-            key.seek_key = eof;
-            f.set_end(key.seek_key + key.n_bytes as i64)?;
+            key.seek_key = f.allocate(key.n_bytes)?;
         }
-        if f.is_big_file() {
+        if big {
             key.rvers += 1000
         }
 
-        key.seek_pdir = f.dir().seek_dir;
+        key.seek_pdir = f.cwd().seek_dir;
 
         Ok(key)
     }
@@ -247,12 +250,14 @@ impl Key {
         class: String,
         cycle: i16,
         buf: Vec<u8>,
+        compression: i32,
         f: &mut RootFile,
     ) -> Result<Self> {
         let indent = name.clone() + "-" + &name;
         trace!(";KEY.new_from_buffer.{indent}.f.end:{:?}", f.end());
         // trace!(";KEY.new_from_buffer.{indent}.buf.value:{:?}", &buf);
-        let key_len = key_len_for(&name, &title, &class, f);
+        let big = f.is_big_file();
+        let key_len = key_len_for(&name, &title, &class, big);
         let obj_len = buf.len() as i32;
         let mut key = Key {
             key_len,
@@ -262,22 +267,21 @@ impl Key {
             cycle,
             obj_len,
             n_bytes: key_len + obj_len,
-            seek_key: f.end(),
-            seek_pdir: f.dir().seek_dir,
+            seek_pdir: f.cwd().seek_dir,
             ..Default::default()
         };
 
-        if f.is_big_file() {
+        if big {
             key.rvers += 1000;
         }
 
-        key.buffer = rcompress::compress(buf, f.compression())?;
+        key.buffer = rcompress::compress(buf, compression)?;
         // trace!(
         //     ";KEY.new_from_buffer.{indent}.buf.after_compression:{:?}",
         //     key.buffer
         // );
         key.n_bytes = key.key_len + key.buffer.len() as i32;
-        f.set_end(key.seek_key + key.n_bytes as i64)?;
+        key.seek_key = f.allocate(key.n_bytes)?;
 
         trace!(
             ";KEY.new_from_buffer.{indent}.key.seek_key:{:?}",
@@ -305,12 +309,12 @@ impl Key {
         f: &RootFile,
     ) -> Self {
         let mut key = Key {
-            key_len: key_len_for(&name, &title, &class, f),
+            key_len: key_len_for(&name, &title, &class, f.is_big_file()),
             name,
             title,
             class,
             cycle,
-            seek_pdir: f.dir().seek_dir,
+            seek_pdir: f.cwd().seek_dir,
             ..Default::default()
         };
         key.n_bytes = key.key_len;
@@ -331,13 +335,14 @@ impl Key {
         f: &mut RootFile,
     ) -> Result<Self>
     where
-        T: FactoryItemWrite,
+        T: Marshaler + Named + RVersioner,
     {
         trace!(";KEY.new_from_object.name:{:?}", name);
         trace!(";KEY.new_from_object.title:{:?}", title);
         trace!(";KEY.new_from_object.class:{:?}", class);
 
-        let key_len = key_len_for(name, title, class, f);
+        let big = f.is_big_file();
+        let key_len = key_len_for(name, title, class, big);
         trace!(";KEY.new_from_object.key_len:{:?}", key_len);
 
         let mut buf = WBuffer::new(key_len as u32);
@@ -353,13 +358,12 @@ impl Key {
             class: class.to_string(),
             name: name.to_string(),
             title: title.to_string(),
-            seek_key: f.end(),
-            seek_pdir: f.dir().seek_dir,
+            seek_pdir: f.cwd().seek_dir,
 
             ..Default::default()
         };
 
-        if f.is_big_file() {
+        if big {
             key.rvers += 1000;
         }
         trace!(
@@ -373,8 +377,7 @@ impl Key {
         );
         key.buffer = buf;
         key.n_bytes = key.key_len + key.buffer.len() as i32;
-
-        f.set_end(key.seek_key + key.n_bytes as i64)?;
+        key.seek_key = f.allocate(key.n_bytes)?;
         Ok(key)
     }
 
@@ -397,6 +400,29 @@ impl Key {
         self.load(file)
     }
 
+    /// Same as [bytes](Key::bytes), from the record of the key (header and payload) already read.
+    pub(crate) fn bytes_from_record(&self, record: &[u8]) -> Result<Vec<u8>> {
+        let invalid = || riofs::Error::InvalidRecord {
+            seek: self.seek_key,
+            reason: format!(
+                "record of {} bytes, key_len = {}, obj_len = {}",
+                record.len(),
+                self.key_len,
+                self.obj_len
+            ),
+        };
+        let key_len = usize::try_from(self.key_len).map_err(|_| invalid())?;
+        let obj_len = usize::try_from(self.obj_len).map_err(|_| invalid())?;
+        let payload = record.get(key_len..).ok_or_else(invalid)?;
+        if self.is_compressed() {
+            let mut buf = vec![0; obj_len];
+            rcompress::decompress(&mut buf, payload)?;
+            return Ok(buf);
+        }
+
+        Ok(payload.get(..obj_len).ok_or_else(invalid)?.to_vec())
+    }
+
     fn load(&self, file: &mut RootFileReader) -> Result<Vec<u8>> {
         if self.is_compressed() {
             let mut buf = vec![0; self.obj_len as usize];
@@ -441,18 +467,13 @@ impl Key {
 
         // self.objarr = *objs.downcast::<rcont::objarray::OBJ_ARRAY>().unwrap();
 
-        let obj = Some(vv);
-
-        if obj
-            .as_ref()
-            .unwrap()
-            .downcast_ref::<TDirectoryFile>()
-            .is_ok()
-        {
-            todo!();
+        if let Ok(dir) = vv.downcast_mut::<TDirectoryFile>() {
+            // the record of a directory does not hold its name, it is the one of its key
+            dir.set_name_title(&self.name, &self.title);
+            dir.read_keys_from(file)?;
         }
 
-        Ok(obj)
+        Ok(Some(vv))
     }
     pub(crate) fn key_len(&self) -> i32 {
         self.key_len
@@ -461,7 +482,7 @@ impl Key {
         self.class = class.to_string();
     }
 
-    pub(crate) fn cycle(&self) -> i32 {
+    pub fn cycle(&self) -> i32 {
         self.cycle as i32
     }
     pub fn n_bytes(&self) -> i32 {
@@ -508,12 +529,22 @@ impl Key {
     pub fn set_cycle(&mut self, cycle: i16) {
         self.cycle = cycle;
     }
+    pub(crate) fn seek_pdir(&self) -> i64 {
+        self.seek_pdir
+    }
+    pub(crate) fn set_seek_pdir(&mut self, seek_pdir: i64) {
+        self.seek_pdir = seek_pdir;
+    }
 }
 
-fn key_len_for(name: &str, title: &str, class: &str, f: &RootFile) -> i32 {
+/// Length of the header of a key, `big` keys have 64 bits seeks.
+///
+/// Whether a key is big is decided before its space is allocated, as in ROOT: a key starting
+/// before `kStartBigFile` can always be addressed with 32 bits.
+fn key_len_for(name: &str, title: &str, class: &str, big: bool) -> i32 {
     let mut nbytes = 22;
 
-    if f.dir().is_big_file() || f.is_big_file() {
+    if big {
         nbytes += 8;
     }
 
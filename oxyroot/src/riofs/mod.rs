@@ -3,10 +3,19 @@ pub(crate) mod consts;
 pub(crate) mod dir;
 mod error;
 pub mod file;
+#[cfg(feature = "http")]
+mod http;
 mod key;
+mod reader;
+pub(crate) mod recover;
+mod verify;
+#[cfg(feature = "xrootd")]
+mod xrootd;
 pub mod utils;
 
 pub(crate) use key::Key;
 
 pub use error::Error;
 pub use error::Result;
+pub use reader::ReaderAt;
+pub use verify::{Problem, VerifyReport};
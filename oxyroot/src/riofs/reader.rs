@@ -0,0 +1,67 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::Mutex;
+
+/// Source of bytes a [RootFile](crate::RootFile) can be read from, equivalent of Go `io.ReaderAt`.
+///
+/// Implemented for in-memory buffers (see [RootFile::from_bytes](crate::RootFile::from_bytes))
+/// and for any [Read] + [Seek] type wrapped in a [Mutex] (see
+/// [RootFile::from_reader](crate::RootFile::from_reader)). Implement it to read ROOT files from
+/// other storages and use [RootFile::from_reader_at](crate::RootFile::from_reader_at).
+///
+/// Sources are shared by the branches of a file and may be read from several threads, see
+/// `Branch::as_par_iter` with the `parallel` feature.
+pub trait ReaderAt: Send + Sync {
+    /// Fill `buf` with the bytes starting at offset `start`.
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()>;
+
+    /// Read several `(start, len)` ranges at once. Remote sources can override it to fetch all
+    /// ranges with a single request.
+    fn read_ranges(&self, ranges: &[(u64, usize)]) -> std::io::Result<Vec<Vec<u8>>> {
+        ranges
+            .iter()
+            .map(|&(start, len)| {
+                let mut buf = vec![0; len];
+                self.read_at(&mut buf, start)?;
+                Ok(buf)
+            })
+            .collect()
+    }
+}
+
+impl<R> ReaderAt for Mutex<R>
+where
+    R: Read + Seek + Send,
+{
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        let mut r = self.lock().unwrap();
+        r.seek(SeekFrom::Start(start))?;
+        r.read_exact(buf)
+    }
+}
+
+/// In-memory content of a ROOT file.
+pub(crate) struct MemReader<T>(pub(crate) T);
+
+impl<T> ReaderAt for MemReader<T>
+where
+    T: AsRef<[u8]> + Send + Sync,
+{
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        let data = self.0.as_ref();
+        let end = start.checked_add(buf.len() as u64);
+        match end {
+            Some(end) if end <= data.len() as u64 => {
+                buf.copy_from_slice(&data[start as usize..end as usize]);
+                Ok(())
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "can not read {} bytes at {start}, buffer has {} bytes",
+                    buf.len(),
+                    data.len()
+                ),
+            )),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::rbytes::rbuffer::RBuffer;
+use crate::riofs::file::RootFileReader;
+use crate::riofs::Key;
+use crate::root::traits::{Named, Object};
+use log::trace;
+
+// fNbytes, version, fObjlen, fDatime, fKeylen: the beginning of each key header
+const KEY_LEN_END: i64 = 16;
+
+/// Basket found when scanning a file, see [scan_records].
+#[derive(Debug, Clone)]
+pub(crate) struct RecoveredBasket {
+    pub(crate) branch: String,
+    pub(crate) tree: String,
+    pub(crate) seek_pdir: i64,
+    pub(crate) seek: i64,
+    pub(crate) n_bytes: i32,
+    pub(crate) entries: i32,
+}
+
+/// Records found when scanning a file.
+#[derive(Debug, Default)]
+pub(crate) struct Scan {
+    /// Keys of the records other than baskets, in the order of the file.
+    pub(crate) keys: Vec<Key>,
+    pub(crate) baskets: Vec<RecoveredBasket>,
+    /// End of the last record read entirely.
+    pub(crate) end: i64,
+}
+
+/// Read the key of each record, one after the other from `begin`, as ROOT `TFile::Recover`.
+/// Gaps, starting with their negated size, and records whose key is invalid are skipped. The scan
+/// stops at the first record which can not be read entirely, as the end of the file being written
+/// when a job crashed.
+pub(crate) fn scan_records(reader: &mut RootFileReader, begin: i64) -> Scan {
+    let mut scan = Scan {
+        end: begin,
+        ..Default::default()
+    };
+    let mut pos = begin;
+    while let Ok(buf) = reader.read_at(pos as u64, 4) {
+        let n_bytes = i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as i64;
+        trace!(";scan_records.{pos}.n_bytes:{:?}", n_bytes);
+        if n_bytes == 0 {
+            break;
+        }
+        if n_bytes < 0 {
+            pos -= n_bytes;
+            continue;
+        }
+        if reader.read_at((pos + n_bytes - 1) as u64, 1).is_err() {
+            break;
+        }
+
+        if !read_record(reader, pos, n_bytes, &mut scan) {
+            trace!(";scan_records.{pos}.invalid_key");
+        }
+        pos += n_bytes;
+        scan.end = pos;
+    }
+    trace!(";scan_records.end:{:?}", scan.end);
+    scan
+}
+
+/// Read the key of the record of `n_bytes` at `pos` into `scan`, false if it is invalid.
+fn read_record(reader: &mut RootFileReader, pos: i64, n_bytes: i64, scan: &mut Scan) -> bool {
+    if n_bytes < KEY_LEN_END {
+        return false;
+    }
+    let key_len = match reader.read_at((pos + KEY_LEN_END - 2) as u64, 2) {
+        Ok(buf) => i16::from_be_bytes([buf[0], buf[1]]) as i64,
+        Err(_) => return false,
+    };
+    if key_len < KEY_LEN_END || key_len > n_bytes {
+        return false;
+    }
+
+    // the header of a basket follows its key, and is counted in fKeylen
+    let head = match reader.read_at(pos as u64, key_len as u64) {
+        Ok(head) => head,
+        Err(_) => return false,
+    };
+    let mut r = RBuffer::new(&head, 0);
+    let key = match r.read_object_into::<Key>() {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    if key.seek_key() != pos {
+        return false;
+    }
+
+    if key.class() == "TBasket" {
+        // TBasket header: version, buffer size, size of the entries offsets, entries
+        let entries = r
+            .read_i16()
+            .and_then(|_| r.read_i32())
+            .and_then(|_| r.read_i32())
+            .and_then(|_| r.read_i32());
+        match entries {
+            Ok(entries) => scan.baskets.push(RecoveredBasket {
+                branch: key.name().to_string(),
+                tree: key.title().to_string(),
+                seek_pdir: key.seek_pdir(),
+                seek: pos,
+                n_bytes: n_bytes as i32,
+                entries,
+            }),
+            Err(_) => return false,
+        }
+    } else {
+        scan.keys.push(key);
+    }
+    true
+}
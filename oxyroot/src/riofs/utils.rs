@@ -1,14 +1,16 @@
 use crate::riofs::Error;
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
 
+/// Split `name;cycle` into its name and cycle, the cycle is 9999 (ie last cycle) when not given.
 pub fn decode_name_cycle(namecycle: &str) -> Result<(&str, u16), Error> {
     let toks: Vec<_> = namecycle.split(';').collect();
 
     match toks.len() {
         1 => Ok((toks[0], 9999)),
-        2 => {
-            unimplemented!()
-        }
+        2 => match toks[1].parse::<u16>() {
+            Ok(cycle) => Ok((toks[0], cycle)),
+            Err(_) => Err(Error::CantDecodeNameCycle(namecycle.to_string())),
+        },
 
         _ => Err(Error::CantDecodeNameCycle(namecycle.to_string())),
     }
@@ -34,3 +36,11 @@ pub(crate) fn datetime_to_u32(datetime: DateTime<Utc>) -> u32 {
 
     ((year - 1995) << 26) | (month << 22) | (day << 17) | (hour << 12) | (minute << 6) | second
 }
+
+/// Inverse of [datetime_to_u32], dates which can not be decoded are replaced by [now].
+pub(crate) fn u32_to_datetime(v: u32) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt((v >> 26) as i32 + 1995, (v >> 22) & 0xf, (v >> 17) & 0x1f)
+        .and_then(|d| d.and_hms_opt((v >> 12) & 0x1f, (v >> 6) & 0x3f, v & 0x3f))
+        .map(|d| d.and_utc())
+        .unwrap_or_else(now)
+}
@@ -0,0 +1,334 @@
+use crate::rbytes::rbuffer::RBuffer;
+use crate::riofs::blocks::FreeSegments;
+use crate::riofs::dir::TDirectoryFile;
+use crate::riofs::file::{RootFileReader, RootFileStreamerInfoContext};
+use crate::riofs::Key;
+use crate::root::traits::{Named, Object};
+use crate::rtree::branch::TBranch;
+use crate::rtree::tree::ReaderTree;
+use log::trace;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// Problem found by [RootFile::verify](crate::RootFile::verify).
+///
+/// Records are designated by a path: `dir/name;cycle` for keys, `dir/tree;cycle/branch[i]` for
+/// the `i`-th basket of a branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The file is shorter than the end written in its header, as files of crashed jobs.
+    Truncated { end: i64 },
+    /// The record of `path` is not inside the file, or can not be read.
+    OutOfFile {
+        path: String,
+        seek: i64,
+        n_bytes: i64,
+    },
+    /// The key at the start of the record of `path` can not be read, or does not describe it.
+    BadKey {
+        path: String,
+        seek: i64,
+        reason: String,
+    },
+    /// The payload of the record of `path` can not be decompressed.
+    BadPayload {
+        path: String,
+        seek: i64,
+        reason: String,
+    },
+    /// The directory or the tree at `path` can not be read.
+    BadObject { path: String, reason: String },
+    /// A segment of the list of free segments is empty or overlaps the previous one.
+    BadFreeSegment { first: i64, last: i64 },
+    /// The records of `path` and `other` share bytes starting at `seek`.
+    Overlap {
+        path: String,
+        other: String,
+        seek: i64,
+    },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Truncated { end } => {
+                write!(f, "file is truncated, its header ends at {end}")
+            }
+            Problem::OutOfFile {
+                path,
+                seek,
+                n_bytes,
+            } => write!(f, "{path}: {n_bytes} bytes at {seek} are not in the file"),
+            Problem::BadKey { path, seek, reason } => {
+                write!(f, "{path}: bad key at {seek}: {reason}")
+            }
+            Problem::BadPayload { path, seek, reason } => {
+                write!(f, "{path}: can not decompress record at {seek}: {reason}")
+            }
+            Problem::BadObject { path, reason } => write!(f, "{path}: can not read: {reason}"),
+            Problem::BadFreeSegment { first, last } => {
+                write!(f, "bad free segment [{first}, {last}]")
+            }
+            Problem::Overlap { path, other, seek } => {
+                write!(f, "{path}: overlaps {other} at {seek}")
+            }
+        }
+    }
+}
+
+/// Result of [RootFile::verify](crate::RootFile::verify).
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Number of keys checked, in all directories.
+    pub n_keys: usize,
+    /// Number of baskets checked, in all trees.
+    pub n_baskets: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl VerifyReport {
+    /// No problem was found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for problem in self.problems.iter() {
+            writeln!(f, "{problem}")?;
+        }
+        write!(
+            f,
+            "{} keys, {} baskets: {} problem(s)",
+            self.n_keys,
+            self.n_baskets,
+            self.problems.len()
+        )
+    }
+}
+
+/// Walk the records of a file, keeping the ones found to check that they do not overlap.
+pub(crate) struct Verifier {
+    reader: RootFileReader,
+    sinfos: RootFileStreamerInfoContext,
+    end: i64,
+    // (seek, n_bytes, path) of the records read, records shared by several objects, as baskets
+    // of the cycles of a tree, are kept once
+    records: Vec<(i64, i64, String)>,
+    seen: HashSet<(i64, i64)>,
+    report: VerifyReport,
+}
+
+impl Verifier {
+    pub(crate) fn new(
+        reader: RootFileReader,
+        sinfos: RootFileStreamerInfoContext,
+        end: i64,
+    ) -> Self {
+        let mut v = Verifier {
+            reader,
+            sinfos,
+            end,
+            records: Vec::new(),
+            seen: HashSet::new(),
+            report: VerifyReport::default(),
+        };
+        if end <= 0 || v.reader.read_at(end as u64 - 1, 1).is_err() {
+            v.report.problems.push(Problem::Truncated { end });
+        }
+        v
+    }
+
+    fn problem(&mut self, problem: Problem) {
+        trace!(";Verifier.problem:{:?}", problem);
+        self.report.problems.push(problem);
+    }
+
+    /// Read the `n_bytes` of the record of `path` at `seek`.
+    pub(crate) fn record(&mut self, path: &str, seek: i64, n_bytes: i64) -> Option<Vec<u8>> {
+        let out_of_file = Problem::OutOfFile {
+            path: path.to_string(),
+            seek,
+            n_bytes,
+        };
+        if seek <= 0 || n_bytes <= 0 || seek + n_bytes > self.end {
+            self.problem(out_of_file);
+            return None;
+        }
+        match self.reader.read_at(seek as u64, n_bytes as u64) {
+            Err(_) => {
+                self.problem(out_of_file);
+                None
+            }
+            Ok(buf) => {
+                if self.seen.insert((seek, n_bytes)) {
+                    self.records.push((seek, n_bytes, path.to_string()));
+                }
+                Some(buf)
+            }
+        }
+    }
+
+    /// Read the record of `path`, starting with a key, and decompress its payload.
+    pub(crate) fn key_record(&mut self, path: &str, seek: i64, n_bytes: i64) -> Option<Key> {
+        let buf = self.record(path, seek, n_bytes)?;
+        let bad_key = |reason: String| Problem::BadKey {
+            path: path.to_string(),
+            seek,
+            reason,
+        };
+
+        let key = match RBuffer::new(&buf, 0).read_object_into::<Key>() {
+            Ok(key) => key,
+            Err(e) => {
+                self.problem(bad_key(format!("{e:?}")));
+                return None;
+            }
+        };
+        if key.n_bytes() as i64 != n_bytes || key.seek_key() != seek {
+            self.problem(bad_key(format!(
+                "key of {} bytes at {}",
+                key.n_bytes(),
+                key.seek_key()
+            )));
+            return None;
+        }
+        if key.key_len() < 0 || key.key_len() > key.n_bytes() || key.obj_len() < 0 {
+            self.problem(bad_key(format!(
+                "key length {} and object length {}",
+                key.key_len(),
+                key.obj_len()
+            )));
+            return None;
+        }
+        if let Err(e) = key.bytes_from_record(&buf) {
+            self.problem(Problem::BadPayload {
+                path: path.to_string(),
+                seek,
+                reason: format!("{e:?}"),
+            });
+            return None;
+        }
+        Some(key)
+    }
+
+    /// Check the record of the top directory, at `begin`. Its key, the one of the file, is at
+    /// least `n_bytes` long.
+    pub(crate) fn top_directory(&mut self, begin: i64, n_bytes: i64) {
+        let key = self
+            .reader
+            .read_at(begin as u64, n_bytes as u64)
+            .ok()
+            .and_then(|buf| RBuffer::new(&buf, 0).read_object_into::<Key>().ok());
+        // an unreadable key is reported by key_record
+        let n_bytes = key.map(|k| k.n_bytes() as i64).unwrap_or(n_bytes);
+        self.key_record("<top directory>", begin, n_bytes);
+    }
+
+    /// Check the keys list of `dir`, at `path`, and the records of its keys.
+    pub(crate) fn directory(&mut self, path: &str, dir: &TDirectoryFile) {
+        trace!(";Verifier.directory.path:{:?}", path);
+        let join = |name: &str| match path {
+            "" => name.to_string(),
+            _ => format!("{path}/{name}"),
+        };
+        let keys_path = join("<keys>");
+        self.key_record(&keys_path, dir.seek_keys, dir.n_bytes_keys as i64);
+
+        for key in dir.keys.iter() {
+            self.report.n_keys += 1;
+            let namecycle = format!("{};{}", key.name(), key.cycle());
+            let key_path = join(&namecycle);
+            let Some(record) = self.key_record(&key_path, key.seek_key(), key.n_bytes() as i64)
+            else {
+                continue;
+            };
+            let class = |k: &Key| k.class().replace("TDirectoryFile", "TDirectory");
+            if record.name() != key.name() || class(&record) != class(key) {
+                self.problem(Problem::BadKey {
+                    path: key_path,
+                    seek: key.seek_key(),
+                    reason: format!("key of {} named {:?}", record.class(), record.name()),
+                });
+                continue;
+            }
+
+            match key.class() {
+                "TDirectoryFile" => {
+                    match dir.get_directory(&namecycle, &mut self.reader, Some(&self.sinfos)) {
+                        Ok(sub) => self.directory(&key_path, &sub),
+                        Err(e) => self.problem(Problem::BadObject {
+                            path: key_path,
+                            reason: format!("{e:?}"),
+                        }),
+                    }
+                }
+                "TTree" => match dir.get_tree(&namecycle, &self.reader, &self.sinfos) {
+                    Ok(tree) => self.tree(&key_path, &tree),
+                    Err(e) => self.problem(Problem::BadObject {
+                        path: key_path,
+                        reason: format!("{e:?}"),
+                    }),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn tree(&mut self, path: &str, tree: &ReaderTree) {
+        for branch in tree.branches_r() {
+            let b: &TBranch = branch.tbranch();
+            for (i, basket) in b.basket_ranges().iter().enumerate() {
+                self.report.n_baskets += 1;
+                if self.seen.contains(&(basket.seek as i64, basket.len as i64)) {
+                    continue;
+                }
+                let basket_path = format!("{path}/{}[{i}]", branch.name());
+                self.key_record(&basket_path, basket.seek as i64, basket.len as i64);
+            }
+        }
+    }
+
+    /// Check that the free segments are sorted and do not overlap the records read.
+    pub(crate) fn free_segments(&mut self, spans: &[FreeSegments]) {
+        let mut previous = None;
+        for span in spans.iter() {
+            if span.first > span.last || matches!(previous, Some(last) if span.first <= last) {
+                self.problem(Problem::BadFreeSegment {
+                    first: span.first,
+                    last: span.last,
+                });
+            }
+            previous = Some(span.last);
+
+            let other = format!("free segment [{}, {}]", span.first, span.last);
+            let overlaps = self
+                .records
+                .iter()
+                .filter(|(seek, n_bytes, _)| *seek <= span.last && seek + n_bytes > span.first)
+                .map(|(seek, _, path)| Problem::Overlap {
+                    path: path.clone(),
+                    other: other.clone(),
+                    seek: (*seek).max(span.first),
+                })
+                .collect::<Vec<_>>();
+            overlaps.into_iter().for_each(|p| self.problem(p));
+        }
+    }
+
+    /// Check that the records read do not overlap.
+    pub(crate) fn finish(mut self) -> VerifyReport {
+        self.records.sort();
+        for (a, b) in self.records.iter().zip(self.records.iter().skip(1)) {
+            if a.0 + a.1 > b.0 {
+                self.report.problems.push(Problem::Overlap {
+                    path: b.2.clone(),
+                    other: a.2.clone(),
+                    seek: b.0,
+                });
+            }
+        }
+        self.report
+    }
+}
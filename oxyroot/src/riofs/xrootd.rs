@@ -0,0 +1,377 @@
+use crate::riofs::reader::ReaderAt;
+use log::trace;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Client for the subset of the XRootD protocol (version 5) needed to read files:
+// handshake, protocol, login, open, stat, read, readv and close requests.
+
+const DEFAULT_PORT: u16 = 1094;
+const PROTOCOL_VERSION: i32 = 0x00000520;
+const MAX_REDIRECTIONS: usize = 8;
+
+// request ids
+const REQ_CLOSE: u16 = 3003;
+const REQ_PROTOCOL: u16 = 3006;
+const REQ_LOGIN: u16 = 3007;
+const REQ_OPEN: u16 = 3010;
+const REQ_READ: u16 = 3013;
+const REQ_STAT: u16 = 3017;
+const REQ_READV: u16 = 3025;
+
+// response status
+const STATUS_OK: u16 = 0;
+const STATUS_OKSOFAR: u16 = 4000;
+const STATUS_ERROR: u16 = 4003;
+const STATUS_REDIRECT: u16 = 4004;
+const STATUS_WAIT: u16 = 4005;
+
+const OPEN_READ: u16 = 0x0010;
+
+// limits of one vector read request
+const READV_MAX_CHUNKS: usize = 1024;
+const READV_MAX_CHUNK_LEN: usize = 2097136;
+const READV_CHUNK_HEADER_LEN: usize = 16;
+
+pub(crate) fn is_xrootd_url(s: &str) -> bool {
+    s.starts_with("root://") || s.starts_with("xroot://")
+}
+
+/// Split `root://host[:port]//path` in host, port and path.
+fn parse_url(url: &str) -> std::io::Result<(String, u16, String)> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid xrootd url: {url}"),
+        )
+    };
+    let rest = url.split_once("://").ok_or_else(invalid)?.1;
+    let (addr, path) = rest.split_once('/').ok_or_else(invalid)?;
+    let (host, port) = match addr.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+        None => (addr, DEFAULT_PORT),
+    };
+    if host.is_empty() || path.is_empty() {
+        return Err(invalid());
+    }
+    // `root://host//abs/path` and `root://host/abs/path` both designate `/abs/path`
+    let path = format!("/{}", path.trim_start_matches('/'));
+    Ok((host.to_string(), port, path))
+}
+
+/// Connect to the first address `addr` resolves to which accepts the connection in `timeout`.
+fn connect(addr: &str, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut err = Error::new(
+        ErrorKind::NotFound,
+        format!("xrootd {addr}: no address found"),
+    );
+    for sock in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&sock, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => err = e,
+        }
+    }
+    Err(err)
+}
+
+enum Reply {
+    Data(Vec<u8>),
+    Redirect { host: String, port: u16 },
+}
+
+/// Connection to a XRootD server, logged in.
+struct Connection {
+    addr: String,
+    stream: TcpStream,
+}
+
+impl Connection {
+    /// Connect to `host:port` and log in, `timeout` is used for the connection and for each read
+    /// or write on it.
+    fn new(host: &str, port: u16, timeout: Duration) -> std::io::Result<Self> {
+        let addr = format!("{host}:{port}");
+        trace!(";xrootd.Connection.new.addr:{:?}", addr);
+        let stream = connect(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+        let mut conn = Connection { addr, stream };
+
+        // handshake
+        let mut hs = Vec::with_capacity(20);
+        for v in [0_i32, 0, 0, 4, 2012] {
+            hs.extend_from_slice(&v.to_be_bytes());
+        }
+        conn.stream.write_all(&hs)?;
+        let (status, _) = conn.read_response()?;
+        if status != STATUS_OK {
+            return Err(conn.error(format!("handshake failed with status {status}")));
+        }
+
+        let mut params = [0_u8; 16];
+        params[..4].copy_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+        conn.call(REQ_PROTOCOL, params, &[])?;
+
+        let mut params = [0_u8; 16];
+        params[..4].copy_from_slice(&(std::process::id() as i32).to_be_bytes());
+        let user = std::env::var("USER").unwrap_or_else(|_| "oxyroot".to_string());
+        let user = user.as_bytes();
+        let n = user.len().min(8);
+        params[4..4 + n].copy_from_slice(&user[..n]);
+        // capver: protocol version 5
+        params[14] = 5;
+        conn.call(REQ_LOGIN, params, &[])?;
+
+        Ok(conn)
+    }
+
+    fn error(&self, msg: String) -> Error {
+        Error::other(format!("xrootd {}: {msg}", self.addr))
+    }
+
+    fn read_response(&mut self) -> std::io::Result<(u16, Vec<u8>)> {
+        let mut hdr = [0_u8; 8];
+        self.stream.read_exact(&mut hdr)?;
+        let status = u16::from_be_bytes([hdr[2], hdr[3]]);
+        let dlen = u32::from_be_bytes([hdr[4], hdr[5], hdr[6], hdr[7]]);
+        let mut data = vec![0; dlen as usize];
+        self.stream.read_exact(&mut data)?;
+        Ok((status, data))
+    }
+
+    /// Send one request and gather its response, waiting if the server asks for it.
+    fn request(&mut self, id: u16, params: [u8; 16], data: &[u8]) -> std::io::Result<Reply> {
+        trace!(";xrootd.Connection.request.id:{:?}", id);
+        let mut req = Vec::with_capacity(24 + data.len());
+        // we never have two requests in flight, stream id is constant
+        req.extend_from_slice(&[0, 1]);
+        req.extend_from_slice(&id.to_be_bytes());
+        req.extend_from_slice(&params);
+        req.extend_from_slice(&(data.len() as i32).to_be_bytes());
+        req.extend_from_slice(data);
+
+        self.stream.write_all(&req)?;
+        let mut ret = Vec::new();
+        loop {
+            let (status, data) = self.read_response()?;
+            match status {
+                STATUS_OK => {
+                    ret.extend(data);
+                    return Ok(Reply::Data(ret));
+                }
+                STATUS_OKSOFAR => ret.extend(data),
+                STATUS_ERROR => {
+                    let errnum = data
+                        .get(..4)
+                        .map(|v| i32::from_be_bytes(v.try_into().unwrap()))
+                        .unwrap_or_default();
+                    let msg = data.get(4..).unwrap_or_default();
+                    let msg = String::from_utf8_lossy(msg);
+                    return Err(self.error(format!(
+                        "request {id} failed with error {errnum}: {}",
+                        msg.trim_end_matches('\0')
+                    )));
+                }
+                STATUS_REDIRECT if data.len() >= 4 => {
+                    let port = i32::from_be_bytes(data[..4].try_into().unwrap());
+                    let host = String::from_utf8_lossy(&data[4..]);
+                    let host = host.split('?').next().unwrap_or_default().to_string();
+                    return Ok(Reply::Redirect {
+                        host,
+                        port: port as u16,
+                    });
+                }
+                STATUS_WAIT if data.len() >= 4 => {
+                    let secs = i32::from_be_bytes(data[..4].try_into().unwrap());
+                    std::thread::sleep(Duration::from_secs(secs.max(1) as u64));
+                    self.stream.write_all(&req)?;
+                }
+                _ => {
+                    return Err(self.error(format!(
+                        "request {id} got unsupported response status {status}"
+                    )))
+                }
+            }
+        }
+    }
+
+    fn call(&mut self, id: u16, params: [u8; 16], data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self.request(id, params, data)? {
+            Reply::Data(data) => Ok(data),
+            Reply::Redirect { host, port } => Err(self.error(format!(
+                "unexpected redirection to {host}:{port} for request {id}"
+            ))),
+        }
+    }
+}
+
+/// Read a remote file served by XRootD, with one connection per file. Requests of several threads
+/// are sent one after the other on the connection.
+pub(crate) struct XRootDReader {
+    url: String,
+    conn: Mutex<Connection>,
+    fhandle: [u8; 4],
+    size: u64,
+}
+
+impl XRootDReader {
+    pub(crate) fn new(url: &str, timeout: Duration) -> std::io::Result<Self> {
+        let (mut host, mut port, path) = parse_url(url)?;
+
+        for _ in 0..MAX_REDIRECTIONS {
+            let mut conn = Connection::new(&host, port, timeout)?;
+
+            let mut params = [0_u8; 16];
+            params[2..4].copy_from_slice(&OPEN_READ.to_be_bytes());
+            match conn.request(REQ_OPEN, params, path.as_bytes())? {
+                Reply::Redirect { host: h, port: p } => {
+                    trace!(";xrootd.XRootDReader.new.redirect:{:?}", (&h, p));
+                    (host, port) = (h, p);
+                }
+                Reply::Data(data) => {
+                    if data.len() < 4 {
+                        return Err(conn.error("invalid open response".to_string()));
+                    }
+                    let fhandle = [data[0], data[1], data[2], data[3]];
+
+                    let mut params = [0_u8; 16];
+                    params[12..16].copy_from_slice(&fhandle);
+                    let stat = conn.call(REQ_STAT, params, &[])?;
+                    // stat info: "id size flags modtime"
+                    let stat = String::from_utf8_lossy(&stat);
+                    let size = stat
+                        .trim_end_matches('\0')
+                        .split_whitespace()
+                        .nth(1)
+                        .and_then(|s| s.parse::<u64>().ok())
+                        .ok_or_else(|| conn.error(format!("invalid stat response: {stat}")))?;
+                    trace!(";xrootd.XRootDReader.new.size:{:?}", size);
+
+                    return Ok(XRootDReader {
+                        url: url.to_string(),
+                        conn: Mutex::new(conn),
+                        fhandle,
+                        size,
+                    });
+                }
+            }
+        }
+
+        Err(Error::other(format!("{url}: too many redirections")))
+    }
+
+    fn check_range(&self, start: u64, len: usize) -> std::io::Result<()> {
+        if start + len as u64 > self.size {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{}: can not read {len} bytes at {start}, file has {} bytes",
+                    self.url, self.size
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// One vector read request, each chunk being smaller than [READV_MAX_CHUNK_LEN].
+    fn read_vectored(&self, ranges: &[(u64, usize)]) -> std::io::Result<Vec<Vec<u8>>> {
+        if ranges.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut list = Vec::with_capacity(ranges.len() * READV_CHUNK_HEADER_LEN);
+        for (start, len) in ranges {
+            list.extend_from_slice(&self.fhandle);
+            list.extend_from_slice(&(*len as i32).to_be_bytes());
+            list.extend_from_slice(&(*start as i64).to_be_bytes());
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let data = conn.call(REQ_READV, [0_u8; 16], &list)?;
+
+        // response is a list of chunk headers, identical to the requested ones, followed by data
+        let mut ret = Vec::with_capacity(ranges.len());
+        let mut data = data.as_slice();
+        for (start, len) in ranges {
+            if data.len() < READV_CHUNK_HEADER_LEN {
+                return Err(conn.error("truncated vector read response".to_string()));
+            }
+            let (hdr, rest) = data.split_at(READV_CHUNK_HEADER_LEN);
+            let rlen = i32::from_be_bytes(hdr[4..8].try_into().unwrap()) as usize;
+            let offset = i64::from_be_bytes(hdr[8..16].try_into().unwrap()) as u64;
+            if offset != *start || rlen != *len || rest.len() < rlen {
+                return Err(conn.error(format!(
+                    "unexpected chunk ({offset}, {rlen}) in vector read response, expected ({start}, {len})"
+                )));
+            }
+            ret.push(rest[..rlen].to_vec());
+            data = &rest[rlen..];
+        }
+        Ok(ret)
+    }
+}
+
+impl ReaderAt for XRootDReader {
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        self.check_range(start, buf.len())?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let mut params = [0_u8; 16];
+        params[..4].copy_from_slice(&self.fhandle);
+        params[4..12].copy_from_slice(&(start as i64).to_be_bytes());
+        params[12..16].copy_from_slice(&(buf.len() as i32).to_be_bytes());
+
+        let mut conn = self.conn.lock().unwrap();
+        let data = conn.call(REQ_READ, params, &[])?;
+        if data.len() != buf.len() {
+            return Err(conn.error(format!(
+                "short read at {start}: {} bytes instead of {}",
+                data.len(),
+                buf.len()
+            )));
+        }
+        buf.copy_from_slice(&data);
+        Ok(())
+    }
+
+    fn read_ranges(&self, ranges: &[(u64, usize)]) -> std::io::Result<Vec<Vec<u8>>> {
+        for (start, len) in ranges {
+            self.check_range(*start, *len)?;
+        }
+
+        let mut ret = Vec::with_capacity(ranges.len());
+        let mut batch = Vec::new();
+        for &(start, len) in ranges {
+            if len > READV_MAX_CHUNK_LEN {
+                ret.extend(self.read_vectored(&batch)?);
+                batch.clear();
+                let mut buf = vec![0; len];
+                self.read_at(&mut buf, start)?;
+                ret.push(buf);
+                continue;
+            }
+            batch.push((start, len));
+            if batch.len() == READV_MAX_CHUNKS {
+                ret.extend(self.read_vectored(&batch)?);
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            ret.extend(self.read_vectored(&batch)?);
+        }
+        Ok(ret)
+    }
+}
+
+impl Drop for XRootDReader {
+    fn drop(&mut self) {
+        let mut params = [0_u8; 16];
+        params[..4].copy_from_slice(&self.fhandle);
+        if let Ok(conn) = self.conn.get_mut() {
+            let _ = conn.call(REQ_CLOSE, params, &[]);
+        }
+    }
+}
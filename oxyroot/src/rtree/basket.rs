@@ -8,6 +8,63 @@ use crate::rtypes::FactoryItemRead;
 use crate::{factory_fn_register_impl, rvers, Branch, Object, RootFile};
 use log::trace;
 
+/// A basket read from its record, with its decompressed data.
+type ReadBasket = crate::riofs::Result<(Basket, BasketData)>;
+
+/// Read and decompress the baskets given by `baskets` as `(seek, n_bytes)` of their record, with
+/// some context `X` passed through. With `parallel` (and the `parallel` feature), baskets are read
+/// and decompressed in batches on the rayon thread pool, each thread reading from its own clone of
+/// `reader`. Baskets are returned in order, with the error of the ones which can not be read or
+/// decompressed.
+pub(crate) fn read_baskets<'a, X: 'a>(
+    reader: RootFileReader,
+    baskets: impl Iterator<Item = (X, i64, i32)> + 'a,
+    parallel: bool,
+) -> Box<dyn Iterator<Item = (X, ReadBasket)> + 'a> {
+    #[cfg(feature = "parallel")]
+    if parallel {
+        use rayon::prelude::*;
+
+        let mut baskets = baskets;
+        let batch = rayon::current_num_threads();
+        let it = std::iter::from_fn(move || {
+            let (ctx, records): (Vec<_>, Vec<_>) = baskets
+                .by_ref()
+                .take(batch)
+                .map(|(x, seek, n_bytes)| (x, (seek, n_bytes)))
+                .unzip();
+            if ctx.is_empty() {
+                return None;
+            }
+            trace!(";read_baskets.batch.len:{:?}", ctx.len());
+            let data: Vec<_> = records
+                .into_par_iter()
+                .map_with(reader.clone(), |reader, (seek, n_bytes)| {
+                    read_basket_data(reader, seek, n_bytes)
+                })
+                .collect();
+            Some(ctx.into_iter().zip(data))
+        });
+        return Box::new(it.flatten());
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = parallel;
+
+    let mut reader = reader;
+    Box::new(
+        baskets.map(move |(x, seek, n_bytes)| (x, read_basket_data(&mut reader, seek, n_bytes))),
+    )
+}
+
+/// Read the basket at `seek`, of `n_bytes` bytes, and decompress its data.
+fn read_basket_data(reader: &mut RootFileReader, seek: i64, n_bytes: i32) -> ReadBasket {
+    let buf = reader.read_basket(seek as u64, n_bytes as u64)?;
+    let mut r = RBuffer::new(&buf, 0);
+    let b = r.read_object_into::<Basket>()?;
+    let data = b.raw_data(&buf)?;
+    Ok((b, data))
+}
+
 #[derive(Debug)]
 pub struct Basket {
     key: crate::riofs::Key,
@@ -99,27 +156,55 @@ impl Basket {
         basket
     }
 
-    pub(crate) fn raw_data(&self, file: &mut RootFileReader) -> BasketData {
-        let ret = self.key.bytes(file, None).unwrap();
+    /// Data of the basket, from `record` holding the whole basket as stored in the file.
+    pub(crate) fn raw_data(&self, record: &[u8]) -> crate::riofs::Result<BasketData> {
+        let ret = self.key.bytes_from_record(record)?;
+        let invalid = |reason: String| crate::riofs::Error::InvalidRecord {
+            seek: self.key.seek_key(),
+            reason,
+        };
 
         if self.border() != self.uncompressed_bytes() {
-            let (data, byte_offsets) = ret.split_at(self.border() as usize);
+            let border = usize::try_from(self.border())
+                .ok()
+                .filter(|&border| border <= ret.len())
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "border {} outside of basket of {} bytes",
+                        self.border(),
+                        ret.len()
+                    ))
+                })?;
+            let (data, byte_offsets) = ret.split_at(border);
 
             let mut bb: Vec<i32> = Vec::with_capacity(byte_offsets.len() / 4);
 
             byte_offsets
-                .chunks(4)
+                .chunks_exact(4)
                 .map(|x| i32::from_be_bytes(x.try_into().unwrap()) - self.key.key_len())
                 .skip(1)
                 .for_each(|x| bb.push(x));
 
             let mut byte_offsets = bb;
-            let last = byte_offsets.len() - 1;
+            let last = byte_offsets
+                .len()
+                .checked_sub(1)
+                .ok_or_else(|| invalid("no entry offsets".to_string()))?;
             byte_offsets[last] = self.border();
-            return BasketData::UnTrustNEntries((self.nev_buf, data.to_vec(), byte_offsets));
+            // offsets are used to slice the data of each entry
+            let in_order = byte_offsets.first().is_some_and(|&first| first >= 0)
+                && byte_offsets.windows(2).all(|w| w[0] <= w[1]);
+            if !in_order {
+                return Err(invalid(format!("bad entry offsets {byte_offsets:?}")));
+            }
+            return Ok(BasketData::UnTrustNEntries((
+                self.nev_buf,
+                data.to_vec(),
+                byte_offsets,
+            )));
         }
 
-        BasketData::TrustNEntries((self.nev_buf, ret))
+        Ok(BasketData::TrustNEntries((self.nev_buf, ret)))
     }
 
     ///         If this ``TBasket`` is embedded within its ``TBranch`` (i.e. must be
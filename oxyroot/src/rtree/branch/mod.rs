@@ -17,6 +17,7 @@ use crate::rtree::streamer_type::type_name_cpp_to_rust;
 use crate::rtypes::FactoryItemRead;
 use log::trace;
 use std::marker::PhantomData;
+use std::ops::Range;
 
 pub(crate) enum BranchChunks {
     RegularSized((i32, i32, Vec<u8>)),
@@ -57,7 +58,7 @@ impl From<Box<dyn FactoryItemRead>> for Branch {
 // }
 
 impl Branch {
-    fn tbranch_mut(&mut self) -> &mut TBranch {
+    pub(crate) fn tbranch_mut(&mut self) -> &mut TBranch {
         match self {
             Branch::Base(ref mut bb) => bb,
             Branch::Element(ref mut be) => &mut be.branch,
@@ -100,7 +101,7 @@ impl Branch {
         }
     }
 
-    pub fn title(&self)->&str{
+    pub fn title(&self) -> &str {
         match self {
             Branch::Base(bb) => bb.title(),
             Branch::Element(be) => be.title(),
@@ -118,6 +119,12 @@ impl Branch {
         b.entries()
     }
 
+    /// Compression settings of the baskets, encoded as in ROOT (`algorithm * 100 + level`)
+    pub fn compression(&self) -> i32 {
+        let b: &TBranch = self.into();
+        b.compress
+    }
+
     /// Get iterator over top-level branches
     pub fn branches(&self) -> impl Iterator<Item = &Branch> {
         match self {
@@ -169,19 +176,42 @@ impl Branch {
         }
     }
 
-    fn get_baskets_buffer(&self) -> Box<dyn Iterator<Item = BranchChunks> + '_> {
+    fn get_baskets_buffer(
+        &self,
+        parallel: bool,
+        entries: Range<i64>,
+    ) -> Box<dyn Iterator<Item = crate::riofs::Result<BranchChunks>> + '_> {
         match self {
-            Branch::Base(bb) => bb.get_baskets_buffer(),
-            Branch::Element(be) => be.get_baskets_buffer(),
+            Branch::Base(bb) => bb.get_baskets_buffer(parallel, entries),
+            Branch::Element(be) => be.get_baskets_buffer(parallel, entries),
         }
     }
 
-    pub fn get_basket<'a, F, T>(&'a self, mut func: F) -> impl Iterator<Item = T> + 'a
+    /// Call `func` on each entry of the branch. Iteration stops at the first basket which can not
+    /// be read.
+    pub fn get_basket<'a, F, T>(&'a self, func: F) -> impl Iterator<Item = T> + 'a
+    where
+        T: 'a,
+        F: FnMut(&mut RBuffer) -> T + 'a,
+    {
+        self.get_basket_impl(func, false, 0..i64::MAX)
+            .map_while(Result::ok)
+    }
+
+    /// Call `func` on the `entries` of the branch, only the baskets holding them are read. The
+    /// error of a basket which can not be read ends the iteration.
+    fn get_basket_impl<'a, F, T>(
+        &'a self,
+        mut func: F,
+        parallel: bool,
+        entries: Range<i64>,
+    ) -> impl Iterator<Item = crate::Result<T>> + 'a
     where
         T: 'a,
         F: FnMut(&mut RBuffer) -> T + 'a,
     {
         trace!("get_basket in BRANCH = {}", self.name());
+        trace!(";Branch.get_basket.entries:{:?}", entries);
 
         let tbranch = match self {
             Branch::Base(bb) => bb,
@@ -195,27 +225,41 @@ impl Branch {
             tbranch.entries()
         );
         assert!(tbranch.reader().is_some());
+        let n = usize::try_from((entries.end - entries.start).max(0)).unwrap_or(usize::MAX);
+        // entries of the first basket before the range are read, then skipped, sub-branches skip
+        // them when zipped
+        let skip = if tbranch.branches().is_empty() {
+            (entries.start - tbranch.first_basket_entry(&entries)).max(0) as usize
+        } else {
+            0
+        };
 
         let it = if !tbranch.branches().is_empty() {
-            let b: Box<dyn Iterator<Item = T>> = Box::new(
-                ZiperBranches::<usize>::new(tbranch.branches(), tbranch.entries() as u32).map(
-                    move |(_n, _chunk_size, buf)| {
+            let b: Box<dyn Iterator<Item = crate::Result<T>>> = Box::new(
+                ZiperBranches::<usize>::new_impl(tbranch.branches(), parallel, entries).map(
+                    move |chunk| {
+                        let (_n, _chunk_size, buf) = chunk?;
                         let mut r = RBuffer::new(&buf, 0);
-                        func(&mut r)
+                        Ok(func(&mut r))
                     },
                 ),
             );
             b
         } else {
-            let b: Box<dyn Iterator<Item = T>> =
-                Box::new(self.get_baskets_buffer().flat_map(move |chunk| {
+            let range_baskets_buffer = self.get_baskets_buffer(parallel, entries);
+            let b: Box<dyn Iterator<Item = crate::Result<T>>> =
+                Box::new(range_baskets_buffer.flat_map(move |chunk| {
+                    let chunk = match chunk {
+                        Ok(chunk) => chunk,
+                        Err(e) => return vec![Err(e.into())],
+                    };
                     match chunk {
                         BranchChunks::RegularSized((n, _chunk_size, buf)) => {
                             let mut r = RBuffer::new(&buf, 0);
                             let mut v = Vec::with_capacity(n as usize);
 
                             for _i in 0..n {
-                                v.push(func(&mut r));
+                                v.push(Ok(func(&mut r)));
                             }
                             v
                         }
@@ -242,7 +286,7 @@ impl Branch {
                                     let mut r = RBuffer::new(buf, 0);
                                     r.set_skip_header(Some(header_bytes));
 
-                                    func(&mut r)
+                                    Ok(func(&mut r))
                                 })
                                 .collect::<Vec<_>>()
                         }
@@ -251,10 +295,38 @@ impl Branch {
             b
         };
 
-        it
+        // only entries read are skipped, not the error of the first basket
+        let mut skip = skip;
+        let it = it.filter(move |v| {
+            let skipped = skip > 0 && v.is_ok();
+            if skipped {
+                skip -= 1;
+            }
+            !skipped
+        });
+        stop_after_error(it.take(n))
+    }
+
+    /// Entries of the column in `entries`, with the error ending the iteration when a basket can
+    /// not be read or an entry can not be decoded.
+    fn read_entries<'a, T>(
+        &'a self,
+        parallel: bool,
+        entries: Range<i64>,
+    ) -> impl Iterator<Item = crate::Result<T>> + 'a
+    where
+        T: UnmarshalerInto<Item = T> + 'a,
+    {
+        let it = self
+            .get_basket_impl(|r| r.read_object_into::<T>(), parallel, entries)
+            .map(|v| Ok(v?.map_err(crate::riofs::Error::from)?));
+        stop_after_error(it)
     }
 
     /// Create an iterator over the data of a column (`TBranch`)
+    ///
+    /// Iteration stops at the first basket which can not be read or decompressed, as in truncated
+    /// files, see [try_as_iter](Branch::try_as_iter) to get the error.
     pub fn as_iter<'a, T>(&'a self) -> crate::Result<impl Iterator<Item = T> + 'a>
     where
         T: UnmarshalerInto<Item = T> + 'a,
@@ -263,6 +335,78 @@ impl Branch {
         // println!("typename of type: {:?}", T::classe_name());
         // println!("typename of type: {:?}", type_name::<T>());
 
+        self.check_item_type::<T>()?;
+        Ok(self.read_entries(false, 0..i64::MAX).map_while(Result::ok))
+    }
+
+    /// Same as [as_iter](Branch::as_iter), but the error of a basket which can not be read or
+    /// decompressed, or of an entry which can not be decoded, is yielded. It is the last item.
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let mut f = RootFile::open("examples/from_uproot/data/HZZ.root").expect("Can not open file");
+    /// let tree = f.get_tree("events").unwrap();
+    /// let njet = tree.branch("NJet").unwrap().try_as_iter::<i32>().unwrap();
+    /// let njet = njet.collect::<oxyroot::Result<Vec<_>>>().unwrap();
+    /// assert_eq!(njet.len(), 2421);
+    /// ```
+    pub fn try_as_iter<'a, T>(
+        &'a self,
+    ) -> crate::Result<impl Iterator<Item = crate::Result<T>> + 'a>
+    where
+        T: UnmarshalerInto<Item = T> + 'a,
+    {
+        self.check_item_type::<T>()?;
+        Ok(self.read_entries(false, 0..i64::MAX))
+    }
+
+    /// Create an iterator over the `entries` of a column, as [as_iter](Branch::as_iter). Only the
+    /// baskets holding these entries are read and decompressed. Entries after the end of the
+    /// branch are not yielded.
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let mut f = RootFile::open("examples/from_uproot/data/HZZ.root").expect("Can not open file");
+    /// let tree = f.get_tree("events").unwrap();
+    /// let njet = tree.branch("NJet").unwrap();
+    /// let all = njet.as_iter::<i32>().unwrap().collect::<Vec<_>>();
+    /// let some = njet.as_iter_range::<i32>(1000..1010).unwrap().collect::<Vec<_>>();
+    /// assert_eq!(some, all[1000..1010]);
+    /// ```
+    pub fn as_iter_range<'a, T>(
+        &'a self,
+        entries: Range<i64>,
+    ) -> crate::Result<impl Iterator<Item = T> + 'a>
+    where
+        T: UnmarshalerInto<Item = T> + 'a,
+    {
+        self.check_item_type::<T>()?;
+        let entries = entries.start.max(0)..entries.end.min(self.entries());
+        Ok(self.read_entries(false, entries).map_while(Result::ok))
+    }
+
+    /// Entry `i` of a column, reading only the basket holding it.
+    pub fn get_entry<T>(&self, i: i64) -> crate::Result<T>
+    where
+        T: UnmarshalerInto<Item = T>,
+    {
+        let out_of_range = || crate::error::Error::EntryOutOfRange {
+            entry: i,
+            entries: self.entries(),
+        };
+        if i < 0 || i >= self.entries() {
+            return Err(out_of_range());
+        }
+        self.check_item_type::<T>()?;
+        self.read_entries::<T>(false, i..i + 1)
+            .next()
+            .ok_or_else(out_of_range)?
+    }
+
+    fn check_item_type<T>(&self) -> crate::Result<()>
+    where
+        T: UnmarshalerInto<Item = T>,
+    {
         let ok_typename = match T::classe_name() {
             None => true,
             Some(tys) => tys.contains(&self.item_type_name()),
@@ -274,7 +418,7 @@ impl Branch {
                 expected: self.item_type_name(),
             })
         } else {
-            Ok(self.get_basket(|r| r.read_object_into::<T>().unwrap()))
+            Ok(())
         }
     }
 
@@ -284,14 +428,29 @@ impl Branch {
     where
         T: UnmarshalerInto<Item = T> + 'a,
     {
-        self.get_basket(|r| r.read_object_into::<T>().unwrap())
+        self.read_entries(false, 0..i64::MAX).map_while(Result::ok)
+    }
+
+    /// Same as [as_iter](Branch::as_iter), but baskets are read and decompressed in parallel on
+    /// the [rayon](https://docs.rs/rayon) thread pool, a few baskets ahead of the entries being
+    /// yielded. Entries are yielded in order, on the calling thread.
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "parallel")))]
+    pub fn as_par_iter<'a, T>(&'a self) -> crate::Result<impl Iterator<Item = T> + 'a>
+    where
+        T: UnmarshalerInto<Item = T> + 'a,
+    {
+        self.check_item_type::<T>()?;
+        Ok(self.read_entries(true, 0..i64::MAX).map_while(Result::ok))
     }
 
     pub fn as_iter_manual<'a, T>(&'a self) -> impl Iterator<Item = T> + 'a
     where
         T: UnmarshalerInto<Item = T> + 'a,
     {
-        self.get_basket(|r| r.read_object_into_with_name::<T>(&self.item_type_name_complete()).unwrap())
+        let name = self.item_type_name_complete();
+        self.get_basket(move |r| r.read_object_into_with_name::<T>(&name).ok())
+            .map_while(|v| v)
     }
 
     pub(crate) fn _streamer_type(&self) -> Option<i32> {
@@ -327,20 +486,32 @@ impl<'a, T> Iterator for ZiperBranchInnerO<'a, T> {
 pub struct ZiperBranches<'a, T> {
     _branches: &'a Vec<Branch>,
     phantom: PhantomData<T>,
-    iterators: Vec<Box<dyn Iterator<Item = BranchChunks> + 'a>>,
+    iterators: Vec<Box<dyn Iterator<Item = crate::riofs::Result<BranchChunks>> + 'a>>,
     // output_buffers: Option<ZiperBranchInnerO<'a, T>>,
     output_buffers: Vec<Option<BranchChunks>>,
     current_size: Vec<usize>,
     nb_entries: Vec<i32>,
+    /// entries to skip in the first chunk of each branch
+    skip: Vec<usize>,
+    /// error of a basket, yielded before ending the iteration
+    error: Option<crate::riofs::Error>,
+    failed: bool,
 }
 
 impl<'a, T> ZiperBranches<'a, T> {
     pub fn new(branches: &'a Vec<Branch>, _nb_entries: u32) -> Self {
+        Self::new_impl(branches, false, 0..i64::MAX)
+    }
+
+    /// Zip the `entries` of `branches`.
+    fn new_impl(branches: &'a Vec<Branch>, parallel: bool, entries: Range<i64>) -> Self {
         let mut v = Vec::new();
+        let mut skip = Vec::new();
         // let mut v: Vec<dyn Iterator<Item = (u32, i32, Vec<u8>)>> = Vec::new();
         for branch in branches {
             let tbranch: &TBranch = branch.into();
-            let data = tbranch.get_baskets_buffer();
+            let data = tbranch.get_baskets_buffer(parallel, entries.clone());
+            skip.push((entries.start - tbranch.first_basket_entry(&entries)).max(0) as usize);
 
             // let b = data.collect::<Vec<_>>();
             //
@@ -363,14 +534,24 @@ impl<'a, T> ZiperBranches<'a, T> {
             output_buffers: Vec::new(),
             current_size: Default::default(),
             nb_entries: Default::default(),
+            skip,
+            error: None,
+            failed: false,
         }
     }
 }
 
 impl<'a, T> ZiperBranches<'a, T> {
     fn fill_output(&mut self) {
-        for it_branch in &mut self.iterators {
+        for (ib, it_branch) in self.iterators.iter_mut().enumerate() {
             if let Some(chunk) = it_branch.next() {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        self.error.get_or_insert(e);
+                        continue;
+                    }
+                };
                 // let (n, chunk_size, buf) = data;
                 // trace!("n = {}", n);
 
@@ -381,7 +562,8 @@ impl<'a, T> ZiperBranches<'a, T> {
 
                 self.output_buffers.push(Some(chunk));
                 self.nb_entries.push(n);
-                self.current_size.push(0);
+                self.current_size
+                    .push(self.skip.get(ib).cloned().unwrap_or(0));
             }
         }
     }
@@ -391,6 +573,13 @@ impl<'a, T> ZiperBranches<'a, T> {
 
         let it_branch = &mut self.iterators[num_branch];
         if let Some(chunk) = it_branch.next() {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    self.error.get_or_insert(e);
+                    return;
+                }
+            };
             let n = match chunk {
                 BranchChunks::RegularSized((n, _, _)) => n,
                 BranchChunks::IrregularSized((n, _, _)) => n,
@@ -406,16 +595,25 @@ impl<'a, T> ZiperBranches<'a, T> {
 }
 
 impl<'a, T> Iterator for ZiperBranches<'a, T> {
-    type Item = (u32, i32, Vec<u8>);
+    type Item = crate::riofs::Result<(u32, i32, Vec<u8>)>;
 
     fn next(&mut self) -> Option<Self::Item> {
         // let mut ret = Vec::new();
 
+        if self.failed {
+            return None;
+        }
+
         if self.output_buffers.is_empty() {
             trace!("self.output_buffers.is_empty()");
             self.fill_output();
         }
 
+        if let Some(e) = self.error.take() {
+            self.failed = true;
+            return Some(Err(e));
+        }
+
         if self.output_buffers.is_empty() {
             return None;
         }
@@ -477,6 +675,19 @@ impl<'a, T> Iterator for ZiperBranches<'a, T> {
             }
         }
 
-        Some((0, size as i32, outbuf))
+        Some(Ok((0, size as i32, outbuf)))
     }
 }
+
+/// End `it` after its first error.
+fn stop_after_error<T>(
+    it: impl Iterator<Item = crate::Result<T>>,
+) -> impl Iterator<Item = crate::Result<T>> {
+    it.scan(false, |failed, v| {
+        if *failed {
+            return None;
+        }
+        *failed = v.is_err();
+        Some(v)
+    })
+}
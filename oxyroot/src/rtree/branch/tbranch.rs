@@ -4,9 +4,10 @@ use crate::rcont::objarray::{ReaderObjArray, WriterObjArray};
 use crate::riofs::file::{RootFileReader, RootFileStreamerInfoContext};
 use crate::root::traits::Named;
 use crate::root::traits::Object;
-use crate::rtree::basket::{Basket, BasketData};
+use crate::rtree::basket::{read_baskets, Basket, BasketData};
 use crate::rtree::branch::tbranch_props::TBranchProps;
 use crate::rtree::branch::BranchChunks;
+use crate::rtree::cache::BasketRange;
 use crate::rtree::leaf::Leaf;
 use crate::rtree::tree::tio_features::TioFeatures;
 use crate::{factory_fn_register_impl, rbase, rvers, Branch, Marshaler, RBuffer, Unmarshaler};
@@ -14,6 +15,7 @@ use itertools::izip;
 use lazy_static::lazy_static;
 use log::trace;
 use regex::Regex;
+use std::ops::Range;
 
 pub(crate) const DEFAULT_BASKET_SIZE: i32 = 32 * 1024;
 // pub(crate) const DEFAULT_SPLIT_LEVEL: i32 = 99;
@@ -104,6 +106,10 @@ impl TBranch {
         &self.branches //.map(|b| b.into())
     }
 
+    pub(crate) fn branches_mut(&mut self) -> &mut Vec<Branch> {
+        &mut self.branches
+    }
+
     pub fn branch(&self, name: &str) -> Option<&Branch> {
         for b in self.branches.iter() {
             if b.name() == name {
@@ -134,7 +140,74 @@ impl TBranch {
         self.sinfos = Some(sinfos);
     }
 
-    pub(crate) fn get_baskets_buffer(&self) -> Box<dyn Iterator<Item = BranchChunks> + '_> {
+    /// Drop the baskets of the branch, which has no entries anymore.
+    pub(crate) fn clear_baskets(&mut self) {
+        self.baskets.clear();
+        self.basket_seek.clear();
+        self.basket_bytes.clear();
+        self.basket_entry = vec![0];
+        self.write_basket = 0;
+        self.entries = 0;
+    }
+
+    /// Add a basket of `entries` entries, of `n_bytes` at `seek`, after the last basket written.
+    /// The basket kept in memory, if any, is dropped: its entries were written in the new one.
+    pub(crate) fn push_basket(&mut self, seek: i64, n_bytes: i32, entries: i32) {
+        let n = self.write_basket as usize;
+        self.basket_seek.truncate(n);
+        self.basket_bytes.truncate(n);
+        self.basket_entry.truncate(n + 1);
+        let first = self.basket_entry.last().cloned().unwrap_or(0);
+        if self.basket_entry.is_empty() {
+            self.basket_entry.push(first);
+        }
+
+        self.baskets.clear();
+        self.basket_seek.push(seek);
+        self.basket_bytes.push(n_bytes);
+        self.basket_entry.push(first + entries as i64);
+        self.write_basket += 1;
+        self.max_baskets = self.max_baskets.max(self.write_basket);
+        self.entries = first + entries as i64;
+    }
+
+    /// Baskets stored in the file, with their entries.
+    pub(crate) fn basket_ranges(&self) -> Vec<BasketRange> {
+        izip!(&self.basket_seek, &self.basket_bytes, &self.basket_entry)
+            .enumerate()
+            .filter(|(_, (_, len, _))| **len > 0)
+            .map(|(i, (seek, len, first))| BasketRange {
+                seek: *seek as u64,
+                len: *len as usize,
+                first: *first,
+                last: self.basket_entry.get(i + 1).cloned().unwrap_or(i64::MAX),
+            })
+            .collect()
+    }
+
+    /// Entries of the `i`-th basket, the basket kept in memory follows the ones in the file.
+    pub(crate) fn basket_entries(&self, i: usize) -> Range<i64> {
+        let first = self.basket_entry.get(i).cloned().unwrap_or(0);
+        let last = self.basket_entry.get(i + 1).cloned().unwrap_or(i64::MAX);
+        first..last
+    }
+
+    /// First entry of the first basket holding some of `entries`.
+    pub(crate) fn first_basket_entry(&self, entries: &Range<i64>) -> i64 {
+        self.basket_entry
+            .iter()
+            .take_while(|e| **e <= entries.start)
+            .last()
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Baskets holding some of `entries`, with all entries of the baskets.
+    pub(crate) fn get_baskets_buffer(
+        &self,
+        parallel: bool,
+        entries: Range<i64>,
+    ) -> Box<dyn Iterator<Item = crate::riofs::Result<BranchChunks>> + '_> {
         trace!(";TBranch.get_baskets_buffer.call:{:?}", true);
         trace!("We are in branch = {}", self.name());
         let mut size_leaves = self.leaves.iter().map(|e| e.etype()).collect::<Vec<_>>();
@@ -179,7 +252,13 @@ impl TBranch {
             self.leaves.len()
         );
 
-        let embedded_basket = if !self.baskets.is_empty() {
+        let (start, end) = (entries.start, entries.end);
+        let overlaps = move |i: usize| {
+            let basket = self.basket_entries(i);
+            basket.start < end && basket.end > start
+        };
+
+        let embedded_basket = if !self.baskets.is_empty() && overlaps(self.basket_seek.len()) {
             assert_eq!(self.baskets.len(), 1);
 
             Some(self.baskets.iter().map(|b| {
@@ -200,15 +279,27 @@ impl TBranch {
             None
         };
 
-        let ret = izip!(&self.basket_seek, &self.basket_bytes, size_leaves, leaves)
-            .filter(|(_start, len, _chunk_size, _leave)| **len > 0)
-            .map(|(start, len, mut chunk_size, leave)| {
+        let reader = self.reader.as_ref().unwrap().clone();
+        let ranges = self
+            .basket_ranges()
+            .into_iter()
+            .filter(|r| r.first < end && r.last > start)
+            .collect();
+        let registration = reader.register_baskets(ranges);
+
+        let baskets = izip!(&self.basket_seek, &self.basket_bytes, size_leaves, leaves)
+            .enumerate()
+            .filter(move |(i, (_start, len, _chunk_size, _leave))| **len > 0 && overlaps(*i))
+            .map(move |(_, (start, len, chunk_size, leave))| {
                 assert_ne!(*len, 0);
-                let mut reader = self.reader.as_ref().unwrap().clone();
-                let buf = reader.read_at(*start as u64, *len as u64).unwrap();
-                let mut r = RBuffer::new(&buf, 0);
-                let b = r.read_object_into::<Basket>().unwrap();
+                // baskets stay registered in the cache while this iterator is alive
+                let _ = &registration;
+                ((chunk_size, leave), *start, *len)
+            });
 
+        let ret = read_baskets(reader, baskets, parallel).map(
+            move |((mut chunk_size, leave), record)| {
+                let (b, data) = record?;
                 trace!(
                     "chunk_size = {}, b.entry_size() = {}",
                     chunk_size,
@@ -223,7 +314,7 @@ impl TBranch {
                     _ => {}
                 }
 
-                match b.raw_data(&mut reader) {
+                Ok(match data {
                     BasketData::TrustNEntries((n, buf)) => {
                         trace!("send ({n},{chunk_size},{:?})", buf);
                         BranchChunks::RegularSized((n, chunk_size, buf))
@@ -266,11 +357,12 @@ impl TBranch {
                           //     BranchChunks::RegularSized((n as i32, chunk_size, buf))
                           // }
                     },
-                }
-            });
+                })
+            },
+        );
         match embedded_basket {
             None => Box::new(ret),
-            Some(before) => Box::new(before.chain(ret)),
+            Some(before) => Box::new(before.map(Ok).chain(ret)),
         }
     }
 
@@ -319,10 +411,8 @@ impl TBranch {
                 },
                 None => panic!("can not be here"),
             };
-
         }
         unknown.to_string()
-
     }
 
     pub fn item_type_name(&self) -> String {
@@ -4,7 +4,7 @@ use crate::rdict::Streamer;
 use crate::rmeta::EReadWrite;
 use crate::root::traits::Named;
 use crate::root::traits::Object as TraitObject;
-use crate::rtree::basket::{Basket, BasketData};
+use crate::rtree::basket::{read_baskets, BasketData};
 use crate::rtree::branch::tbranch_props::TBranchProps;
 use crate::rtree::branch::{BranchChunks, TBranch};
 use crate::rtree::leaf::Leaf;
@@ -16,6 +16,7 @@ use lazy_static::lazy_static;
 use log::trace;
 use regex::Regex;
 use std::iter::once;
+use std::ops::Range;
 
 #[derive(Default, Debug)]
 pub struct TBranchElement {
@@ -188,7 +189,6 @@ impl TBranchElement {
         }
 
         self.branch.item_type_name_complete()
-
     }
 
     pub fn item_type_name(&self) -> String {
@@ -301,7 +301,12 @@ impl TBranchElement {
         RE.captures(self.name()).unwrap().get(2).unwrap().as_str()
     }
 
-    pub(crate) fn get_baskets_buffer(&self) -> Box<dyn Iterator<Item = BranchChunks> + '_> {
+    /// Baskets holding some of `entries`, see [TBranch::get_baskets_buffer].
+    pub(crate) fn get_baskets_buffer(
+        &self,
+        parallel: bool,
+        entries: Range<i64>,
+    ) -> Box<dyn Iterator<Item = crate::riofs::Result<BranchChunks>> + '_> {
         trace!(";TBranchElement.get_baskets_buffer.call:{:?}", true);
         let mut size_leaves = self
             .branch
@@ -347,7 +352,14 @@ impl TBranchElement {
             size_leaves
         );
 
-        let embedded_basket = if !self.branch.baskets.is_empty() {
+        let (start, end) = (entries.start, entries.end);
+        let overlaps = move |i: usize| {
+            let basket = self.branch.basket_entries(i);
+            basket.start < end && basket.end > start
+        };
+
+        let in_memory = overlaps(self.branch.basket_seek.len());
+        let embedded_basket = if !self.branch.baskets.is_empty() && in_memory {
             assert_eq!(self.branch.baskets.len(), 1);
 
             let element = self.streamer();
@@ -425,19 +437,31 @@ impl TBranchElement {
             leaves
         );
 
-        let ret = izip!(
+        let reader = self.branch.reader().as_ref().unwrap().clone();
+        let ranges = self
+            .branch
+            .basket_ranges()
+            .into_iter()
+            .filter(|r| r.first < end && r.last > start)
+            .collect();
+        let registration = reader.register_baskets(ranges);
+
+        let baskets = izip!(
             &self.branch.basket_seek,
             &self.branch.basket_bytes,
             size_leaves,
             leaves
         )
-        .filter(|(_start, len, _chunk_size, _leave)| **len > 0)
-        .map(|(start, len, mut chunk_size, leave)| {
-            let mut reader = self.branch.reader().as_ref().unwrap().clone();
-            let buf = reader.read_at(*start as u64, *len as u64).unwrap();
-            let mut r = RBuffer::new(&buf, 0);
-            let b = r.read_object_into::<Basket>().unwrap();
+        .enumerate()
+        .filter(move |(i, (_start, len, _chunk_size, _leave))| **len > 0 && overlaps(*i))
+        .map(move |(_, (start, len, chunk_size, leave))| {
+            // baskets stay registered in the cache while this iterator is alive
+            let _ = &registration;
+            ((chunk_size, leave), *start, *len)
+        });
 
+        let ret = read_baskets(reader, baskets, parallel).map(move |((mut chunk_size, leave), record)| {
+            let (b, data) = record?;
             trace!(
                 "chunk_size = {}, b.entry_size() = {}",
                 chunk_size,
@@ -462,7 +486,7 @@ impl TBranchElement {
                 self.streamer_type(),
             );
 
-            match b.raw_data(&mut reader) {
+            Ok(match data {
                 BasketData::TrustNEntries((n, buf)) => {
                     trace!("send ({n},{chunk_size},{:?})", buf);
                     BranchChunks::RegularSized((n, chunk_size, buf))
@@ -527,11 +551,11 @@ impl TBranchElement {
                         BranchChunks::RegularSized((n as i32, chunk_size, buf))
                     }
                 },
-            }
+            })
         });
         match embedded_basket {
             None => Box::new(ret),
-            Some(before) => Box::new(before.chain(ret)),
+            Some(before) => Box::new(before.map(Ok).chain(ret)),
         }
     }
 }
@@ -18,6 +18,8 @@ where
     branch: Branch,
     iterator: Box<dyn Iterator<Item = T>>,
     basket: Option<WBasket>,
+    /// values written, kept for the index of the tree, see [WriterTree::build_index]
+    integers: Option<Vec<i64>>,
 }
 
 impl<T> WBranch<T>
@@ -60,7 +62,8 @@ where
         let mut tbanch = TBranch::new(name.to_string());
 
         tbanch.iobits = tree.iobits();
-        // branch.compress = f.compression();
+        // inherited from tree or file when writing
+        tbanch.compress = -1;
         tbanch.basket_size = DEFAULT_BASKET_SIZE;
         tbanch.max_baskets = DEFAULT_MAX_BASKETS;
         tbanch.basket_entry.push(0);
@@ -101,6 +104,7 @@ where
             branch,
             iterator: Box::new(it),
             basket: None,
+            integers: None,
         };
         // branch.basket = Some(branch.create_new_basket(tree, None));
         branch.branch.tbranch_mut().leaves.push(leaf);
@@ -143,6 +147,10 @@ where
 
                 // basket.wbuf.write_object(&item).unwrap();
                 let sz_new = basket.wbuf.len();
+                if let Some(integers) = self.integers.as_mut() {
+                    let bytes = &basket.wbuf.p()[sz_old..sz_new];
+                    integers.extend(tbranch.leaves[0].integer_from_bytes(bytes));
+                }
                 trace!(";WBranch.write.{ident}.sz_new:{:?}", sz_new);
                 let n = (sz_new - sz_old) as i32;
                 if n > basket.basket.nev_size {
@@ -161,6 +169,36 @@ where
         Ok(ret)
     }
 
+    /// Keep the values written, the branch holds one integer per entry.
+    pub(crate) fn record_integers(&mut self) -> crate::Result<()> {
+        let leaf = &self.branch.tbranch().leaves[0];
+        if !leaf.is_integer() {
+            return Err(crate::error::Error::TypeMismatch {
+                expected: "integer".to_string(),
+                given: self.branch.item_type_name(),
+            });
+        }
+        self.integers = Some(Vec::new());
+        Ok(())
+    }
+
+    /// Values written since [record_integers](WBranch::record_integers).
+    pub(crate) fn take_integers(&mut self) -> Vec<i64> {
+        self.integers.take().unwrap_or_default()
+    }
+
+    /// Set compression settings of the branch, as in ROOT `TBranch::SetCompressionSettings`.
+    pub(crate) fn set_compression(&mut self, compression: i32) {
+        self.branch.tbranch_mut().compress = compression;
+    }
+
+    /// Use `compression` if no settings were set for this branch.
+    pub(crate) fn inherit_compression(&mut self, compression: i32) {
+        if self.branch.tbranch().compress < 0 {
+            self.set_compression(compression);
+        }
+    }
+
     fn create_new_basket(&mut self, tree: &WriterTree, f: &RootFile) -> WBasket {
         trace!(";WBranch.create_new_basket.call:{:?}", true);
         trace!(
@@ -183,10 +221,23 @@ where
         WBasket::new(basket)
     }
 
+    /// Write the basket being filled at the end of a cluster, the next entry starts a new basket.
+    pub(crate) fn flush_cluster(&mut self, file: &mut RootFile) -> crate::riofs::Result<()> {
+        if self.basket.as_ref().is_some_and(|b| b.basket.nev_buf > 0) {
+            self.flush(file)?;
+            self.basket = None;
+        }
+        Ok(())
+    }
+
     pub(crate) fn flush(&mut self, file: &mut RootFile) -> crate::riofs::Result<()> {
         trace!(";WBranch.flush.call:{:?}", true);
-        let basket = self.basket.as_mut().unwrap();
-        let b = basket.write_to_file(file)?;
+        // the last cluster ended with the last entry
+        let Some(basket) = self.basket.as_mut() else {
+            return Ok(());
+        };
+        let compression = self.branch.tbranch().compress;
+        let b = basket.write_to_file(file, compression)?;
         trace!(";WBranch.flush.basket_bytes_writter:{:?}", b);
         let BasketBytesWritten {
             tot_bytes,
@@ -0,0 +1,190 @@
+use log::trace;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+// ranges separated by less than this are read at once, gap included
+const MAX_GAP: u64 = 16 * 1024;
+// maximal size of one merged read
+const MAX_READ: usize = 64 * 1024 * 1024;
+// maximal size of baskets fetched in advance
+const MAX_CACHE: usize = 256 * 1024 * 1024;
+
+/// Position of a basket in the file with its range of entries `[first, last)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BasketRange {
+    pub(crate) seek: u64,
+    pub(crate) len: usize,
+    pub(crate) first: i64,
+    pub(crate) last: i64,
+}
+
+/// Merge `(start, len)` ranges close to each other into larger reads. Returned reads are sorted
+/// and cover all ranges.
+pub(crate) fn plan_reads(ranges: &[(u64, usize)]) -> Vec<(u64, usize)> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort();
+
+    let mut reads: Vec<(u64, usize)> = Vec::new();
+    for (start, len) in ranges {
+        if let Some((beg, n)) = reads.last_mut() {
+            let end = *beg + *n as u64;
+            let new_end = end.max(start + len as u64);
+            if start <= end + MAX_GAP && (new_end - *beg) as usize <= MAX_READ {
+                *n = (new_end - *beg) as usize;
+                continue;
+            }
+        }
+        reads.push((start, len));
+    }
+    reads
+}
+
+/// Equivalent of ROOT `TTreeCache`, shared by the branches of one tree.
+///
+/// Branches being iterated register their baskets. When a basket is missing, all baskets of the
+/// registered branches sharing entries with it are fetched with a few merged reads, so branches
+/// read together do not seek back and forth in the file.
+#[derive(Default)]
+pub(crate) struct BasketCache {
+    branches: HashMap<usize, Vec<BasketRange>>,
+    next_id: usize,
+    data: HashMap<u64, Vec<u8>>,
+    order: VecDeque<u64>,
+    size: usize,
+}
+
+impl BasketCache {
+    fn register(&mut self, baskets: Vec<BasketRange>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.branches.insert(id, baskets);
+        id
+    }
+
+    fn unregister(&mut self, id: usize) {
+        if let Some(baskets) = self.branches.remove(&id) {
+            for b in baskets {
+                self.remove(b.seek);
+            }
+        }
+    }
+
+    fn remove(&mut self, seek: u64) -> Option<Vec<u8>> {
+        let data = self.data.remove(&seek)?;
+        self.size -= data.len();
+        Some(data)
+    }
+
+    /// Take basket out of the cache, as each basket is read once by its branch.
+    pub(crate) fn take(&mut self, seek: u64, len: usize) -> Option<Vec<u8>> {
+        match self.data.get(&seek) {
+            Some(data) if data.len() == len => self.remove(seek),
+            _ => None,
+        }
+    }
+
+    /// Baskets to read along with the one at `seek`: baskets of registered branches sharing
+    /// entries with it and not already in cache.
+    pub(crate) fn plan(&self, seek: u64, len: usize) -> Vec<(u64, usize)> {
+        let mut ret = vec![(seek, len)];
+        let basket = self
+            .branches
+            .values()
+            .flatten()
+            .find(|b| b.seek == seek && b.len == len);
+
+        if let Some(basket) = basket {
+            for b in self.branches.values().flatten() {
+                if b.seek != seek
+                    && b.first < basket.last
+                    && basket.first < b.last
+                    && !self.data.contains_key(&b.seek)
+                {
+                    ret.push((b.seek, b.len));
+                }
+            }
+        }
+        trace!(";BasketCache.plan.{seek}.baskets:{:?}", ret.len());
+        ret
+    }
+
+    pub(crate) fn insert(&mut self, seek: u64, data: Vec<u8>) {
+        self.size += data.len();
+        if let Some(old) = self.data.insert(seek, data) {
+            self.size -= old.len();
+        } else {
+            self.order.push_back(seek);
+        }
+
+        while self.size > MAX_CACHE {
+            match self.order.pop_front() {
+                Some(old) => {
+                    self.remove(old);
+                }
+                None => break,
+            }
+        }
+        if self.order.len() > 2 * self.data.len() {
+            let data = &self.data;
+            self.order.retain(|seek| data.contains_key(seek));
+        }
+    }
+}
+
+/// Baskets of a branch registered in a [BasketCache], unregistered when dropped.
+pub(crate) struct BasketRegistration {
+    cache: Arc<Mutex<BasketCache>>,
+    id: usize,
+}
+
+impl BasketRegistration {
+    pub(crate) fn new(cache: Arc<Mutex<BasketCache>>, baskets: Vec<BasketRange>) -> Self {
+        let id = cache.lock().unwrap().register(baskets);
+        Self { cache, id }
+    }
+}
+
+impl Drop for BasketRegistration {
+    fn drop(&mut self) {
+        self.cache.lock().unwrap().unregister(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_reads() {
+        let reads = plan_reads(&[(300, 100), (100, 100), (200, 50), (100_000, 10)]);
+        assert_eq!(reads, vec![(100, 300), (100_000, 10)]);
+
+        let reads = plan_reads(&[(0, MAX_READ), (MAX_READ as u64, 10)]);
+        assert_eq!(reads, vec![(0, MAX_READ), (MAX_READ as u64, 10)]);
+    }
+
+    #[test]
+    fn plan_baskets_sharing_entries() {
+        let cache = Arc::new(Mutex::new(BasketCache::default()));
+        let basket = |seek, first, last| BasketRange {
+            seek,
+            len: 10,
+            first,
+            last,
+        };
+        let _a =
+            BasketRegistration::new(cache.clone(), vec![basket(100, 0, 10), basket(300, 10, 20)]);
+        let b = BasketRegistration::new(cache.clone(), vec![basket(110, 0, 20)]);
+
+        let mut plan = cache.lock().unwrap().plan(300, 10);
+        plan.sort();
+        assert_eq!(plan, vec![(110, 10), (300, 10)]);
+
+        cache.lock().unwrap().insert(110, vec![0; 10]);
+        assert_eq!(cache.lock().unwrap().plan(300, 10), vec![(300, 10)]);
+
+        drop(b);
+        assert!(cache.lock().unwrap().take(110, 10).is_none());
+        assert_eq!(cache.lock().unwrap().plan(100, 10), vec![(100, 10)]);
+    }
+}
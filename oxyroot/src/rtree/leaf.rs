@@ -180,6 +180,31 @@ impl Leaf {
         }
     }
 
+    /// Leaf of one integer, as `int32_t` or `uint64_t`.
+    pub(crate) fn is_integer(&self) -> bool {
+        matches!(self, Leaf::B(_) | Leaf::S(_) | Leaf::I(_) | Leaf::L(_)) && self.tleaf().len == 1
+    }
+
+    /// Value of an integer leaf, see [is_integer](Leaf::is_integer), from its `bytes` written in a
+    /// basket. Unsigned 64 bits values above `i64::MAX` wrap, as in ROOT `Long64_t`.
+    pub(crate) fn integer_from_bytes(&self, bytes: &[u8]) -> Option<i64> {
+        if !self.is_integer() {
+            return None;
+        }
+        let unsigned = self.tleaf().unsigned;
+        let v = match bytes.len() {
+            1 if unsigned => bytes[0] as i64,
+            1 => bytes[0] as i8 as i64,
+            2 if unsigned => u16::from_be_bytes(bytes.try_into().ok()?) as i64,
+            2 => i16::from_be_bytes(bytes.try_into().ok()?) as i64,
+            4 if unsigned => u32::from_be_bytes(bytes.try_into().ok()?) as i64,
+            4 => i32::from_be_bytes(bytes.try_into().ok()?) as i64,
+            8 => i64::from_be_bytes(bytes.try_into().ok()?),
+            _ => return None,
+        };
+        Some(v)
+    }
+
     pub(crate) fn write_to_buffer(
         &mut self,
         w: &mut WBuffer,
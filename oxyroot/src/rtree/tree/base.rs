@@ -1,5 +1,6 @@
 use crate::rbase;
 use crate::riofs::file::RootFileStreamerInfoContext;
+use crate::rtree::tree::index::TreeIndex;
 use crate::rtree::tree::tio_features::TioFeatures;
 
 #[derive(Default)]
@@ -51,6 +52,8 @@ pub struct Tree<B> {
 
     pub(crate) clusters: Clusters,
     pub(crate) iobits: TioFeatures,
+    /// index of the entries by the values of two branches
+    pub(crate) index: Option<TreeIndex>,
 
     pub(crate) branches: Vec<B>,
 
@@ -92,6 +95,7 @@ impl<B> Default for Tree<B> {
             estimate: 0,
             clusters: Clusters::default(),
             iobits: TioFeatures::default(),
+            index: None,
             branches: Vec::new(),
             sinfos: None,
         }
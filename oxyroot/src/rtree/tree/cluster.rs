@@ -0,0 +1,128 @@
+use crate::rbytes::UnmarshalerInto;
+use crate::rtree::tree::base::{Clusters, Tree};
+use crate::Branch;
+use log::trace;
+use std::ops::Range;
+
+// size of the read cache assumed by ROOT to estimate the size of clusters of trees without
+// auto-flush
+const DEFAULT_CACHE_SIZE: i128 = 30000000;
+
+/// Entries of the clusters of a tree, as ROOT `TTree::TClusterIterator`.
+///
+/// Clusters are described by ranges: the clusters of the range `i` have `sizes[i]` entries, up to
+/// the entry `ranges[i]`. After the last range, clusters have `auto_flush` entries when it is
+/// positive, or a size estimated from the compressed size of the tree.
+pub(crate) struct ClusterIterator<'a> {
+    clusters: &'a Clusters,
+    auto_flush: i64,
+    entries: i64,
+    zip_bytes: i64,
+    /// current cluster range, `clusters.ranges.len()` after the last range
+    range: usize,
+    next: i64,
+}
+
+impl ClusterIterator<'_> {
+    fn estimated_size(&self) -> i64 {
+        if self.auto_flush > 0 {
+            return self.auto_flush;
+        }
+        if self.zip_bytes <= 0 {
+            return (self.entries - 1).max(1);
+        }
+        let size = self.entries as i128 * DEFAULT_CACHE_SIZE / self.zip_bytes as i128;
+        size.clamp(1, i64::MAX as i128) as i64
+    }
+}
+
+impl Iterator for ClusterIterator<'_> {
+    type Item = Range<i64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next;
+        if start >= self.entries {
+            return None;
+        }
+
+        let ranges = &self.clusters.ranges;
+        let mut next = if !ranges.is_empty() || self.auto_flush > 0 {
+            while self.range < ranges.len() && start > ranges[self.range] {
+                self.range += 1;
+            }
+            match self.clusters.sizes.get(self.range) {
+                Some(&size) if self.range < ranges.len() => {
+                    let size = if size > 0 {
+                        size
+                    } else {
+                        self.estimated_size()
+                    };
+                    // the last cluster of a range may be partial
+                    start.saturating_add(size).min(ranges[self.range] + 1)
+                }
+                _ => start.saturating_add(self.estimated_size()),
+            }
+        } else {
+            // trees written before cluster ranges or without auto-flush
+            start.saturating_add(self.estimated_size())
+        };
+        next = next.clamp(start + 1, self.entries);
+        trace!(";ClusterIterator.next:{:?}", start..next);
+
+        self.next = next;
+        Some(start..next)
+    }
+}
+
+impl<B> Tree<B> {
+    pub(crate) fn clusters(&self) -> ClusterIterator<'_> {
+        ClusterIterator {
+            clusters: &self.clusters,
+            auto_flush: self.auto_flush,
+            entries: self.entries,
+            zip_bytes: self.zip_bytes,
+            range: 0,
+            next: 0,
+        }
+    }
+}
+
+/// Entries of one cluster of a tree, with the branches to read, given by
+/// [ReaderTree::for_each_cluster](crate::ReaderTree::for_each_cluster).
+///
+/// The baskets of the branches are aligned on clusters: reading a chunk only reads the baskets of
+/// its cluster, independently of the other chunks.
+pub struct ClusterChunk<'a> {
+    entries: Range<i64>,
+    branches: Vec<&'a Branch>,
+}
+
+impl<'a> ClusterChunk<'a> {
+    pub(crate) fn new(entries: Range<i64>, branches: Vec<&'a Branch>) -> Self {
+        Self { entries, branches }
+    }
+
+    /// Entries of the cluster.
+    pub fn entries(&self) -> Range<i64> {
+        self.entries.clone()
+    }
+
+    /// Branch `name` of the chunk.
+    pub fn branch(&self, name: &str) -> Option<&'a Branch> {
+        self.branches.iter().find(|b| b.name() == name).copied()
+    }
+
+    /// Iterator over the entries of the cluster of branch `name`, see
+    /// [Branch::as_iter_range](crate::Branch::as_iter_range).
+    pub fn as_iter<T>(&self, name: &str) -> crate::Result<impl Iterator<Item = T> + 'a>
+    where
+        T: UnmarshalerInto<Item = T> + 'a,
+    {
+        let branch = self
+            .branch(name)
+            .ok_or_else(|| crate::error::Error::BranchNotFound {
+                name: name.to_string(),
+            })?;
+        branch.as_iter_range::<T>(self.entries())
+    }
+}
@@ -0,0 +1,119 @@
+use crate::rbytes::rbuffer::RBuffer;
+use crate::rbytes::wbuffer::WBuffer;
+use crate::rbytes::{ensure_maximum_supported_version, RVersioner, Unmarshaler};
+use crate::root::traits::Object;
+use crate::{factory_fn_register_impl, rbase, root, rvers, Marshaler};
+
+/// Rust equivalent of ROOT `TFriendElement`, a friend of a tree as recorded in the file by
+/// `TTree::AddFriend`.
+///
+/// Friends are resolved by [RootFile::get_tree](crate::RootFile::get_tree), see
+/// [ReaderTree::friend_elements](crate::ReaderTree::friend_elements).
+#[derive(Default, Debug, Clone)]
+pub struct FriendElement {
+    named: rbase::Named,
+    tree_name: String,
+}
+
+impl FriendElement {
+    /// Friend tree `tree_name` of the file `file_name`, known as `alias`. An empty `file_name`
+    /// designates the file of the tree.
+    pub fn new<S: Into<String>>(alias: S, tree_name: S, file_name: S) -> Self {
+        Self {
+            named: rbase::Named::new(alias, file_name),
+            tree_name: tree_name.into(),
+        }
+    }
+
+    /// Name of the friend used to prefix its branches, the name of the friend tree unless an
+    /// alias was given.
+    pub fn alias(&self) -> &str {
+        &self.named.name
+    }
+
+    /// Path of the friend tree in its file.
+    pub fn tree_name(&self) -> &str {
+        &self.tree_name
+    }
+
+    /// File of the friend tree, as given to `TTree::AddFriend`. ROOT also records the name of
+    /// the file of the tree when the friend is in the same file.
+    pub fn file_name(&self) -> &str {
+        &self.named.title
+    }
+}
+
+impl root::traits::Named for FriendElement {
+    fn name(&self) -> &'_ str {
+        self.alias()
+    }
+
+    fn title(&self) -> &'_ str {
+        self.file_name()
+    }
+}
+
+impl RVersioner for FriendElement {
+    fn rversion(&self) -> i16 {
+        rvers::FRIEND_ELEMENT
+    }
+}
+
+factory_fn_register_impl!(FriendElement, "TFriendElement");
+
+impl Unmarshaler for FriendElement {
+    fn unmarshal(&mut self, r: &mut RBuffer) -> crate::rbytes::Result<()> {
+        let hdr = r.read_header(self.class())?;
+        ensure_maximum_supported_version(hdr.vers, rvers::FRIEND_ELEMENT, self.class())?;
+
+        r.read_object(&mut self.named)?;
+        self.tree_name = r.read_string()?.to_string();
+
+        r.check_header(&hdr)?;
+        Ok(())
+    }
+}
+
+impl Marshaler for FriendElement {
+    fn marshal(&self, w: &mut WBuffer) -> crate::rbytes::Result<i64> {
+        let hdr = w.write_header(self.class(), Self::rversion(self))?;
+        w.write_object(&self.named)?;
+        w.write_string(&self.tree_name)?;
+        w.set_header(hdr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FriendElement;
+    use crate::rbytes::rbuffer::RBuffer;
+    use crate::rbytes::wbuffer::WBuffer;
+    use crate::rcont::list::{ReaderList, WriterList};
+    use crate::Marshaler;
+
+    #[test]
+    fn friend_elements_in_list() {
+        let friends = [
+            FriendElement::new("calib", "calibration", "calib.root"),
+            FriendElement::new("T", "dir/T", ""),
+        ];
+        let mut list = WriterList::new();
+        friends.iter().for_each(|f| list.add(f));
+        let mut w = WBuffer::new(0);
+        list.marshal(&mut w).unwrap();
+
+        let data = w.buffer();
+        let mut r = RBuffer::new(&data, 0);
+        let list = r.read_object_into::<ReaderList>().unwrap();
+        assert_eq!(list.len(), 2);
+        let calib = list.at::<FriendElement>(0);
+        assert_eq!(calib.alias(), "calib");
+        assert_eq!(calib.tree_name(), "calibration");
+        assert_eq!(calib.file_name(), "calib.root");
+        let t = list.at::<FriendElement>(1);
+        assert_eq!(
+            (t.alias(), t.tree_name(), t.file_name()),
+            ("T", "dir/T", "")
+        );
+    }
+}
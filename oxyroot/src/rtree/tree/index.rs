@@ -0,0 +1,189 @@
+use crate::rbytes::rbuffer::RBuffer;
+use crate::rbytes::wbuffer::WBuffer;
+use crate::rbytes::{ensure_maximum_supported_version, RVersioner, Unmarshaler};
+use crate::root::traits::Object;
+use crate::{factory_fn_register_impl, rbase, root, rvers, Marshaler};
+use log::trace;
+
+/// Rust equivalent of ROOT `TTreeIndex`, the entries of a tree sorted by the values of a major
+/// and a minor branch, as built by `TTree::BuildIndex("run", "event")`.
+///
+/// Read with [ReaderTree::index](crate::ReaderTree::index), written with
+/// [WriterTree::build_index](crate::WriterTree::build_index).
+#[derive(Default, Debug, Clone)]
+pub struct TreeIndex {
+    /// base `TVirtualIndex`
+    named: rbase::Named,
+    major_name: String,
+    minor_name: String,
+    /// (major, minor) values of the entries, in increasing order
+    values: Vec<(i64, i64)>,
+    /// entry of each value
+    entries: Vec<i64>,
+}
+
+impl TreeIndex {
+    /// Index of the entries with values `major[i]` and `minor[i]`.
+    pub(crate) fn new(major_name: &str, minor_name: &str, major: &[i64], minor: &[i64]) -> Self {
+        let n = major.len().min(minor.len());
+        let mut entries = (0..n).collect::<Vec<_>>();
+        // stable, the first entry of equal values is found first
+        entries.sort_by_key(|&i| (major[i], minor[i]));
+        Self {
+            major_name: major_name.to_string(),
+            minor_name: minor_name.to_string(),
+            values: entries.iter().map(|&i| (major[i], minor[i])).collect(),
+            entries: entries.into_iter().map(|i| i as i64).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Name of the major branch, or expression for indexes built by ROOT.
+    pub fn major_name(&self) -> &str {
+        &self.major_name
+    }
+
+    /// Name of the minor branch, `"0"` when the index was built without minor values.
+    pub fn minor_name(&self) -> &str {
+        &self.minor_name
+    }
+
+    /// Number of indexed entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry with the values `major` and `minor`, the first one if several entries have them,
+    /// as ROOT `TTreeIndex::GetEntryNumberWithIndex`.
+    pub fn entry_with_index(&self, major: i64, minor: i64) -> Option<i64> {
+        let i = self.values.partition_point(|&v| v < (major, minor));
+        trace!(";TreeIndex.entry_with_index.i:{:?}", i);
+        match self.values.get(i) {
+            Some(&v) if v == (major, minor) => Some(self.entries[i]),
+            _ => None,
+        }
+    }
+}
+
+impl root::traits::Named for TreeIndex {
+    fn name(&self) -> &'_ str {
+        &self.named.name
+    }
+
+    fn title(&self) -> &'_ str {
+        &self.named.title
+    }
+}
+
+impl RVersioner for TreeIndex {
+    fn rversion(&self) -> i16 {
+        rvers::TREE_INDEX
+    }
+}
+
+factory_fn_register_impl!(TreeIndex, "TTreeIndex");
+
+impl Unmarshaler for TreeIndex {
+    fn unmarshal(&mut self, r: &mut RBuffer) -> crate::rbytes::Result<()> {
+        let hdr = r.read_header(self.class())?;
+        ensure_maximum_supported_version(hdr.vers, rvers::TREE_INDEX, self.class())?;
+
+        {
+            let hdr = r.read_header("TVirtualIndex")?;
+            ensure_maximum_supported_version(hdr.vers, rvers::VIRTUAL_INDEX, "TVirtualIndex")?;
+            r.read_object(&mut self.named)?;
+            r.check_header(&hdr)?;
+        }
+
+        self.major_name = r.read_string()?.to_string();
+        self.minor_name = r.read_string()?.to_string();
+        let n = usize::try_from(r.read_i64()?)?;
+        trace!(";TreeIndex.unmarshal.n:{:?}", n);
+        let mut major = vec![0; n];
+        r.read_array_i64(&mut major)?;
+        self.values = if hdr.vers > 1 {
+            let mut minor = vec![0; n];
+            r.read_array_i64(&mut minor)?;
+            major.into_iter().zip(minor).collect()
+        } else {
+            // major and minor values were packed in one value
+            major
+                .into_iter()
+                .map(|v| (v >> 31, v & 0x7fffffff))
+                .collect()
+        };
+        self.entries = vec![0; n];
+        r.read_array_i64(&mut self.entries)?;
+
+        r.check_header(&hdr)?;
+        Ok(())
+    }
+}
+
+impl Marshaler for TreeIndex {
+    fn marshal(&self, w: &mut WBuffer) -> crate::rbytes::Result<i64> {
+        let hdr = w.write_header(self.class(), Self::rversion(self))?;
+        {
+            let hdr = w.write_header("TVirtualIndex", rvers::VIRTUAL_INDEX)?;
+            w.write_object(&self.named)?;
+            w.set_header(hdr)?;
+        }
+        w.write_string(&self.major_name)?;
+        w.write_string(&self.minor_name)?;
+        w.write_i64(self.entries.len().try_into()?)?;
+        let (major, minor): (Vec<_>, Vec<_>) = self.values.iter().cloned().unzip();
+        w.write_array_i64(&major)?;
+        w.write_array_i64(&minor)?;
+        w.write_array_i64(&self.entries)?;
+        w.set_header(hdr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeIndex;
+    use crate::rbytes::rbuffer::RBuffer;
+    use crate::rbytes::wbuffer::WBuffer;
+    use crate::rvers;
+    use crate::{rbase, Marshaler};
+
+    #[test]
+    fn tree_index_v1() {
+        // values of (run, event): (2, 1), (1, 5), (2, 0)
+        let mut w = WBuffer::new(0);
+        let hdr = w.write_header("TTreeIndex", 1).unwrap();
+        let vhdr = w
+            .write_header("TVirtualIndex", rvers::VIRTUAL_INDEX)
+            .unwrap();
+        w.write_object(&rbase::Named::default()).unwrap();
+        w.set_header(vhdr).unwrap();
+        w.write_string("run").unwrap();
+        w.write_string("event").unwrap();
+        w.write_i64(3).unwrap();
+        w.write_array_i64(&[(1 << 31) + 5, 2 << 31, (2 << 31) + 1])
+            .unwrap();
+        w.write_array_i64(&[1, 2, 0]).unwrap();
+        w.set_header(hdr).unwrap();
+
+        let data = w.buffer();
+        let mut r = RBuffer::new(&data, 0);
+        let index = r.read_object_into::<TreeIndex>().unwrap();
+        assert_eq!((index.major_name(), index.minor_name()), ("run", "event"));
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.entry_with_index(1, 5), Some(1));
+        assert_eq!(index.entry_with_index(2, 0), Some(2));
+        assert_eq!(index.entry_with_index(2, 1), Some(0));
+        assert_eq!(index.entry_with_index(1, 0), None);
+
+        let mut w = WBuffer::new(0);
+        index.marshal(&mut w).unwrap();
+        let data = w.buffer();
+        let mut r = RBuffer::new(&data, 0);
+        let index = r.read_object_into::<TreeIndex>().unwrap();
+        assert_eq!(index.entry_with_index(2, 1), Some(0));
+    }
+}
@@ -8,12 +8,21 @@ pub mod branch_name;
 
 pub mod base;
 
+pub mod cluster;
+
+pub mod friend;
+
+pub mod index;
+
 // pub struct TioFeatures {
 //     val:
 // }
 // pub type WriterTree<T> = Tree<WBranch<T>>;
 
 pub use base::Tree;
+pub use cluster::ClusterChunk;
+pub use friend::FriendElement;
+pub use index::TreeIndex;
 pub use reader::ReaderTree;
 pub use writer::StateCallBack;
 pub use writer::WriterTree;
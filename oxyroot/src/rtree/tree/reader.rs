@@ -2,10 +2,16 @@ use crate::rbytes::{ensure_maximum_supported_version, ensure_minimum_supported_v
 use crate::rcont::list::ReaderList;
 use crate::rcont::objarray::ReaderObjArray;
 use crate::riofs::file::{RootFileReader, RootFileStreamerInfoContext};
+use crate::riofs::recover::RecoveredBasket;
+use crate::rtree::branch::TBranch;
 use crate::rtree::tree::base::Tree;
+use crate::rtree::tree::cluster::ClusterChunk;
+use crate::rtree::tree::friend::FriendElement;
+use crate::rtree::tree::index::TreeIndex;
 use crate::rvers;
-use crate::{factory_all_for_register_impl, Branch, Object, RBuffer, Unmarshaler};
+use crate::{factory_all_for_register_impl, rbase, Branch, Named, Object, RBuffer, Unmarshaler};
 use log::trace;
+use std::ops::Range;
 
 /// Read only Rust equivalent of [`TTree`](https://root.cern/doc/master/classTTree.html)
 ///
@@ -15,11 +21,20 @@ pub struct ReaderTree {
     tree: Tree<Branch>,
     reader: Option<RootFileReader>,
     user_infos: Option<ReaderList>,
+    friend_elements: Vec<FriendElement>,
+    /// friend trees with their alias
+    friends: Vec<(String, ReaderTree)>,
+    /// friends which could not be read, with the error
+    unresolved_friends: Vec<(FriendElement, String)>,
+    /// aliases, named by the alias with the expression as title
+    aliases: Vec<rbase::Named>,
 }
 
 impl ReaderTree {
     pub(crate) fn set_reader(&mut self, reader: Option<RootFileReader>) {
         if let Some(r) = &reader {
+            // branches of the tree share the same basket cache
+            let r = r.with_basket_cache();
             for b in self.tree.branches.iter_mut() {
                 b.set_reader(Some(r.clone()));
             }
@@ -34,8 +49,59 @@ impl ReaderTree {
         self.tree.sinfos = Some(sinfos);
     }
 
-    /// Get a branch from this tree
+    /// Drop the baskets of all branches, see
+    /// [RootFile::recover_tree_from](crate::RootFile::recover_tree_from).
+    pub(crate) fn clear_baskets(&mut self) {
+        for_each_tbranch(&mut self.tree.branches, &mut |b| b.clear_baskets());
+        self.tree.entries = 0;
+    }
+
+    /// Add the `baskets` of this tree, found by scanning the file, to their branches when they
+    /// were written after the last basket of the branch. Baskets of branches whose name is not
+    /// unique in the tree can not be attributed and are ignored.
+    pub(crate) fn append_baskets(&mut self, baskets: &[RecoveredBasket]) {
+        let tree = self.tree.named.name().to_string();
+        let names = self
+            .branches_r()
+            .iter()
+            .map(|b| b.name().to_string())
+            .collect::<Vec<_>>();
+        let mut appended = false;
+        let mut entries: Option<i64> = None;
+        for_each_tbranch(&mut self.tree.branches, &mut |b| {
+            if names.iter().filter(|n| *n == b.name()).count() == 1 {
+                let name = b.name().to_string();
+                let n = b.write_basket as usize;
+                let last = b.basket_seek.iter().take(n).max().cloned().unwrap_or(0);
+                for basket in baskets
+                    .iter()
+                    .filter(|k| k.tree == tree && k.branch == name && k.seek > last)
+                {
+                    trace!(";ReaderTree.append_baskets.{name}.seek:{:?}", basket.seek);
+                    b.push_basket(basket.seek, basket.n_bytes, basket.entries);
+                    appended = true;
+                }
+            }
+            if b.write_basket > 0 {
+                entries = Some(entries.map_or(b.entries, |e| e.min(b.entries)));
+            }
+        });
+        // branches may hold more entries than the tree, the ones of their last basket
+        if appended {
+            self.tree.entries = entries.unwrap_or(0);
+        }
+    }
+
+    /// Get a branch from this tree, from an alias renaming a branch, see
+    /// [aliases](ReaderTree::aliases), or from its friends, see
+    /// [add_friend](ReaderTree::add_friend).
     pub fn branch(&self, name: &str) -> Option<&Branch> {
+        self.own_branch(name)
+            .or_else(|| self.alias_branch(name))
+            .or_else(|| self.friend_branch(name))
+    }
+
+    fn own_branch(&self, name: &str) -> Option<&Branch> {
         for b in self.tree.branches.iter() {
             if b.name() == name {
                 return Some(b);
@@ -48,6 +114,164 @@ impl ReaderTree {
         None
     }
 
+    /// Branch renamed by the alias `name`, aliases of aliases are followed.
+    fn alias_branch(&self, name: &str) -> Option<&Branch> {
+        let mut name = name;
+        // an alias can not be followed more than once without looping
+        for _ in 0..self.aliases.len() {
+            name = self.alias(name)?.trim();
+            if !is_branch_name(name) {
+                return None;
+            }
+            trace!(";ReaderTree.alias_branch.name:{:?}", name);
+            if let Some(b) = self.own_branch(name).or_else(|| self.friend_branch(name)) {
+                return Some(b);
+            }
+        }
+        None
+    }
+
+    /// Branch `alias.name` or `name` of a friend, as ROOT `TTree::GetBranch`.
+    fn friend_branch(&self, name: &str) -> Option<&Branch> {
+        for (alias, friend) in self.friends.iter() {
+            let Some(name) = name.strip_prefix(alias.as_str()) else {
+                continue;
+            };
+            if let Some(b) = name.strip_prefix('.').and_then(|name| friend.branch(name)) {
+                return Some(b);
+            }
+        }
+        self.friends
+            .iter()
+            .find_map(|(_, friend)| friend.branch(name))
+    }
+
+    /// Make `friend` a friend of this tree, as ROOT `TTree::AddFriend`: its branches are found
+    /// by [branch](ReaderTree::branch) as `alias.name`, or `name` when this tree has no branch
+    /// `name`. The alias is the name of `friend` when not given, a friend with the same alias is
+    /// replaced.
+    ///
+    /// Entries of friends are matched by number: `friend` is expected to have the entries of
+    /// this tree, in the same order. This is not checked, the branches of a friend with fewer
+    /// entries have fewer entries than the ones of this tree.
+    ///
+    /// Friends recorded in the file by ROOT are added by
+    /// [RootFile::get_tree](crate::RootFile::get_tree).
+    ///
+    /**
+    ```
+    use oxyroot::{RootFile, WriterTree};
+    let s = "/tmp/add_friend.root";
+    {
+        let mut file = RootFile::create(s).expect("Can not create file");
+        let mut tree = WriterTree::new("events");
+        tree.new_branch("n", 0..10);
+        tree.write(&mut file).expect("Can not write tree");
+        let mut tree = WriterTree::new("weights");
+        tree.new_branch("w", (0..10).map(|i| i as f64 / 2.0));
+        tree.write(&mut file).expect("Can not write tree");
+        file.close().expect("Can not close file");
+    }
+
+    let mut file = RootFile::open(s).unwrap();
+    let mut tree = file.get_tree("events").unwrap();
+    tree.add_friend(file.get_tree("weights").unwrap(), Some("wgt"));
+    let w = tree.branch("wgt.w").unwrap().as_iter::<f64>().unwrap();
+    assert_eq!(w.sum::<f64>(), 22.5);
+    assert!(tree.branch("w").is_some());
+    ```
+     */
+    pub fn add_friend(&mut self, friend: ReaderTree, alias: Option<&str>) {
+        let alias = alias.unwrap_or(friend.tree.named.name()).to_string();
+        trace!(";ReaderTree.add_friend.alias:{:?}", alias);
+        self.friends.retain(|(a, _)| *a != alias);
+        self.friends.push((alias, friend));
+    }
+
+    /// Friend `alias` of this tree, see [add_friend](ReaderTree::add_friend).
+    pub fn friend(&self, alias: &str) -> Option<&ReaderTree> {
+        self.friends
+            .iter()
+            .find(|(a, _)| a == alias)
+            .map(|(_, friend)| friend)
+    }
+
+    /// Friends of this tree with their alias, see [add_friend](ReaderTree::add_friend).
+    pub fn friends(&self) -> impl Iterator<Item = (&str, &ReaderTree)> {
+        self.friends.iter().map(|(a, friend)| (a.as_str(), friend))
+    }
+
+    /// Friends recorded in the file by ROOT `TTree::AddFriend`, including the ones which could
+    /// not be found by [RootFile::get_tree](crate::RootFile::get_tree).
+    pub fn friend_elements(&self) -> &[FriendElement] {
+        &self.friend_elements
+    }
+
+    pub(crate) fn add_unresolved_friend(&mut self, element: FriendElement, error: String) {
+        self.unresolved_friends.push((element, error));
+    }
+
+    /// Friends recorded in the file which could not be read by
+    /// [RootFile::get_tree](crate::RootFile::get_tree), with the error, as a missing friend
+    /// file. Their branches are not found by [branch](ReaderTree::branch).
+    pub fn unresolved_friends(&self) -> impl Iterator<Item = (&FriendElement, &str)> {
+        self.unresolved_friends
+            .iter()
+            .map(|(element, error)| (element, error.as_str()))
+    }
+
+    /// Index of the entries built by ROOT `TTree::BuildIndex` or
+    /// [WriterTree::build_index](crate::WriterTree::build_index).
+    pub fn index(&self) -> Option<&TreeIndex> {
+        self.tree.index.as_ref()
+    }
+
+    /// Entry whose major and minor values are `major` and `minor`, as ROOT
+    /// `TTree::GetEntryNumberWithIndex`, `None` if the tree has no [index](ReaderTree::index) or
+    /// no such entry. The entry can then be read with [Branch::get_entry].
+    pub fn entry_with_index(&self, major: i64, minor: i64) -> Option<i64> {
+        self.index()?.entry_with_index(major, minor)
+    }
+
+    /// Aliases of the tree, as `(name, expression)`, defined by ROOT `TTree::SetAlias` or
+    /// [set_alias](ReaderTree::set_alias).
+    ///
+    /// Expressions, as `sqrt(px*px+py*py)`, are not evaluated. Aliases whose expression is the
+    /// name of a branch, as `pt := muon_pt`, are resolved by [branch](ReaderTree::branch).
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|a| (a.name.as_str(), a.title.as_str()))
+    }
+
+    /// Expression of the alias `name`, as ROOT `TTree::GetAlias`.
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.title.as_str())
+    }
+
+    /// Define the alias `name` of `expression`, as ROOT `TTree::SetAlias`, an alias with the same
+    /// name is replaced.
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let mut f = RootFile::open("examples/from_uproot/data/HZZ.root").expect("Can not open file");
+    /// let mut tree = f.get_tree("events").unwrap();
+    /// tree.set_alias("n_muons", "NMuon");
+    /// tree.set_alias("muon_pt", "sqrt(Muon_Px*Muon_Px+Muon_Py*Muon_Py)");
+    /// let n = tree.branch("n_muons").unwrap().as_iter::<i32>().unwrap();
+    /// assert_eq!(n.sum::<i32>(), 3825);
+    /// assert!(tree.branch("muon_pt").is_none());
+    /// ```
+    pub fn set_alias(&mut self, name: &str, expression: &str) {
+        match self.aliases.iter_mut().find(|a| a.name == name) {
+            Some(alias) => alias.title = expression.to_string(),
+            None => self.aliases.push(rbase::Named::new(name, expression)),
+        }
+    }
+
     /// Get iterator over top-level branches
     pub fn branches(&self) -> impl Iterator<Item = &Branch> {
         self.tree.branches.iter()
@@ -58,6 +282,56 @@ impl ReaderTree {
         self.tree.entries
     }
 
+    /// Entries of the clusters of the tree, in order, as ROOT `TTree::TClusterIterator`. The
+    /// baskets of all branches start at cluster boundaries, so that clusters can be read
+    /// independently, for example by several threads each opening the file.
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let mut f = RootFile::open("examples/from_uproot/data/HZZ.root").expect("Can not open file");
+    /// let tree = f.get_tree("events").unwrap();
+    /// let clusters = tree.clusters().collect::<Vec<_>>();
+    /// assert_eq!(clusters.first().unwrap().start, 0);
+    /// assert_eq!(clusters.last().unwrap().end, tree.entries());
+    /// ```
+    pub fn clusters(&self) -> impl Iterator<Item = Range<i64>> + '_ {
+        self.tree.clusters()
+    }
+
+    /// Call `f` with a [ClusterChunk] for each cluster of the tree, see
+    /// [clusters](ReaderTree::clusters), holding the `branches` to read.
+    ///
+    /// ```
+    /// use oxyroot::RootFile;
+    /// let mut f = RootFile::open("examples/from_uproot/data/HZZ.root").expect("Can not open file");
+    /// let tree = f.get_tree("events").unwrap();
+    /// let mut n_muons = 0;
+    /// tree.for_each_cluster(&["NMuon"], |chunk| {
+    ///     n_muons += chunk.as_iter::<i32>("NMuon").unwrap().sum::<i32>();
+    /// })
+    /// .expect("Can not find branches");
+    /// assert_eq!(n_muons, 3825);
+    /// ```
+    pub fn for_each_cluster<F>(&self, branches: &[&str], mut f: F) -> crate::Result<()>
+    where
+        F: FnMut(ClusterChunk<'_>),
+    {
+        let branches = branches
+            .iter()
+            .map(|name| {
+                self.branch(name)
+                    .ok_or_else(|| crate::error::Error::BranchNotFound {
+                        name: name.to_string(),
+                    })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        for entries in self.clusters() {
+            trace!(";ReaderTree.for_each_cluster.entries:{:?}", entries);
+            f(ClusterChunk::new(entries, branches.clone()));
+        }
+        Ok(())
+    }
+
     /// Get all (recursively) branches in this tree
     pub fn branches_r(&self) -> Vec<&Branch> {
         let mut v = Vec::new();
@@ -130,6 +404,14 @@ impl ReaderTree {
     // }
 }
 
+/// Error for an object of class `found` read where an object of class `expected` is expected.
+fn wrong_class(expected: &str, found: &str) -> crate::rbytes::Error {
+    crate::rbytes::Error::WrongClass {
+        expected: expected.to_string(),
+        found: found.to_string(),
+    }
+}
+
 impl Unmarshaler for ReaderTree {
     fn unmarshal(&mut self, r: &mut RBuffer) -> crate::rbytes::Result<()> {
         let _beg = r.pos();
@@ -260,38 +542,66 @@ impl Unmarshaler for ReaderTree {
         if hdr.vers > 5 {
             // tree.aliases
             let v = r.read_object_any_into()?;
-            if v.is_some() {
-                todo!()
+            if let Some(v) = v {
+                let mut aliases = *v
+                    .downcast::<ReaderList>()
+                    .map_err(|e| wrong_class("TList", e.into_object().class()))?;
+                while !aliases.is_empty() {
+                    let alias = aliases
+                        .remove(0)
+                        .downcast::<rbase::Named>()
+                        .map_err(|e| wrong_class("TNamed", e.into_object().class()))?;
+                    self.aliases.push(*alias);
+                }
+                trace!(
+                    ";Tree.unmarshal.a{_beg}.aliases.len: {}",
+                    self.aliases.len()
+                );
             }
         }
 
         {
-            //tree.indexValues
-            let v = r.read_object_any_into()?;
-            if v.is_some() {
-                todo!()
+            //tree.indexValues, TArrayD of indexes built before the TTreeIndex, skipped
+            let n = r.read_i32()?;
+            for _ in 0..n {
+                r.read_f64()?;
             }
+            trace!(";Tree.unmarshal.{_beg}.index_values.len: {}", n);
         }
 
         {
-            //tree.index
-            let v = r.read_object_any_into()?;
-            if v.is_some() {
-                todo!()
-            }
+            //tree.index, TArrayI
+            let mut index = vec![0; usize::try_from(r.read_i32()?)?];
+            r.read_array_i32(&mut index)?;
         }
 
         if hdr.vers > 5 {
             //tree.treeindex
             let v = r.read_object_any_into()?;
-            if v.is_some() {
-                todo!()
+            if let Some(v) = v {
+                // other indexes, as the TChainIndex of chains, are not read
+                if let Ok(index) = v.downcast::<TreeIndex>() {
+                    self.tree.index = Some(*index);
+                }
             }
 
             //tree.friends
             let v = r.read_object_any_into()?;
-            if v.is_some() {
-                todo!()
+            if let Some(v) = v {
+                let mut friends = *v
+                    .downcast::<ReaderList>()
+                    .map_err(|e| wrong_class("TList", e.into_object().class()))?;
+                while !friends.is_empty() {
+                    let friend = friends
+                        .remove(0)
+                        .downcast::<FriendElement>()
+                        .map_err(|e| wrong_class("TFriendElement", e.into_object().class()))?;
+                    self.friend_elements.push(*friend);
+                }
+                trace!(
+                    ";Tree.unmarshal.a{_beg}.friends.len: {}",
+                    self.friend_elements.len()
+                );
             }
 
             trace!(";Tree.unmarshal.{}.pos_before_user_info: {}", _beg, r.pos());
@@ -325,3 +635,21 @@ impl Unmarshaler for ReaderTree {
 }
 
 factory_all_for_register_impl!(ReaderTree, "TTree", rvers::TREE);
+
+/// Call `f` on the branches and, recursively, on their sub-branches.
+fn for_each_tbranch(branches: &mut [Branch], f: &mut impl FnMut(&mut TBranch)) {
+    for b in branches.iter_mut() {
+        let b = b.tbranch_mut();
+        f(b);
+        for_each_tbranch(b.branches_mut(), f);
+    }
+}
+
+/// `name` is the name of a branch, as `muon_pt` or `evt.x`, rather than an expression.
+fn is_branch_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
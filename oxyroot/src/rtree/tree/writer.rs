@@ -1,9 +1,13 @@
+use crate::error::{Error, Result};
 use crate::rbytes::{RVersioner, WBuffer};
+use crate::rcompress::CompressionSettings;
 use crate::rcont::objarray::WriterObjArray;
 use crate::rdict::StreamerInfo;
+use crate::riofs::dir::WriteDestination;
 use crate::riofs::file::RootFileStreamerInfoContext;
 use crate::rtree::branch::wbranch::WBranch;
 use crate::rtree::tree::base::Tree;
+use crate::rtree::tree::index::TreeIndex;
 use crate::rtree::tree::tio_features::TioFeatures;
 use crate::{rbase, rvers, Marshaler, Named, Object, RootFile};
 use log::trace;
@@ -29,6 +33,9 @@ file.close().expect("Can not close file");
 pub struct WriterTree {
     tree: Tree<WBranch<Box<dyn Marshaler>>>,
     callbacks: Vec<Box<dyn FnMut(StateCallBack)>>,
+    compression: Option<CompressionSettings>,
+    /// major and minor branches of the index to build, see build_index
+    index_branches: Option<(String, Option<String>)>,
 }
 
 /// Argument for callbacks called before and during writing branches. Callbacks are mainly used to
@@ -66,6 +73,8 @@ impl WriterTree {
                 ..Default::default()
             },
             callbacks: Vec::new(),
+            compression: None,
+            index_branches: None,
         }
     }
 
@@ -82,6 +91,140 @@ impl WriterTree {
         self.callbacks.push(f);
     }
 
+    /// Set compression settings of the baskets of all branches of this tree. By default, the
+    /// compression settings of the file are used.
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        self.compression = Some(settings);
+    }
+
+    /// Set compression settings of the baskets of one branch, overriding the ones of the tree and
+    /// the file. Equivalent of ROOT `TBranch::SetCompressionSettings`.
+    ///
+    /**
+    ```
+    use oxyroot::{CompressionAlgorithm, CompressionSettings, RootFile, WriterTree};
+    let mut file = RootFile::create("/tmp/mixed_compression.root").expect("Can not create file");
+    let mut tree = WriterTree::new("mytree");
+    tree.set_compression(CompressionSettings::new(CompressionAlgorithm::Lzma, 9));
+    tree.new_branch("hot", 0..1000);
+    tree.new_branch("archive", 0..1000);
+    tree.set_branch_compression("hot", CompressionSettings::new(CompressionAlgorithm::LZ4, 4))
+        .expect("Can not find branch");
+    tree.write(&mut file).expect("Can not write tree");
+    file.close().expect("Can not close file");
+    ```
+     */
+    pub fn set_branch_compression<S>(
+        &mut self,
+        name: S,
+        settings: CompressionSettings,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+    {
+        let name = name.as_ref();
+        let branch = self
+            .tree
+            .branches
+            .iter_mut()
+            .find(|b| b.name() == name)
+            .ok_or_else(|| Error::BranchNotFound {
+                name: name.to_string(),
+            })?;
+        branch.set_compression(settings.to_root());
+        Ok(())
+    }
+
+    /// Build the index of the entries by the values of the branches `major` and `minor`, as ROOT
+    /// `TTree::BuildIndex`, when the tree is written. Without `minor`, entries are indexed by the
+    /// values of `major` only. Branches must hold one integer per entry, they are added with
+    /// [new_branch](WriterTree::new_branch) before.
+    ///
+    /// Entries are then found with [ReaderTree::entry_with_index](crate::ReaderTree::entry_with_index).
+    ///
+    /**
+    ```
+    use oxyroot::{RootFile, WriterTree};
+    let s = "/tmp/build_index.root";
+    {
+        let mut file = RootFile::create(s).expect("Can not create file");
+        let mut tree = WriterTree::new("events");
+        tree.new_branch("run", (0..100).map(|i| 300 - i / 10));
+        tree.new_branch("event", (0..100_i64).map(|i| i % 10 * 1000));
+        tree.build_index("run", Some("event")).expect("Can not find branches");
+        tree.write(&mut file).expect("Can not write tree");
+        file.close().expect("Can not close file");
+    }
+
+    let tree = RootFile::open(s).unwrap().get_tree("events").unwrap();
+    assert_eq!(tree.entry_with_index(300, 0), Some(0));
+    assert_eq!(tree.entry_with_index(298, 7000), Some(27));
+    assert_eq!(tree.entry_with_index(298, 7001), None);
+    ```
+     */
+    pub fn build_index(&mut self, major: &str, minor: Option<&str>) -> Result<()> {
+        for name in std::iter::once(major).chain(minor) {
+            self.tree
+                .branches
+                .iter_mut()
+                .find(|b| b.name() == name)
+                .ok_or_else(|| Error::BranchNotFound {
+                    name: name.to_string(),
+                })?
+                .record_integers()?;
+        }
+        self.index_branches = Some((major.to_string(), minor.map(|m| m.to_string())));
+        Ok(())
+    }
+
+    /// Index of the entries written, see [build_index](WriterTree::build_index).
+    fn make_index(&mut self) -> Option<TreeIndex> {
+        let (major_name, minor_name) = self.index_branches.as_ref()?;
+        let mut values = |name: &str| {
+            let b = self.tree.branches.iter_mut().find(|b| b.name() == name);
+            b.map(|b| b.take_integers()).unwrap_or_default()
+        };
+        let major = values(major_name);
+        let (minor_name, minor) = match minor_name {
+            Some(name) if name == major_name => (name.as_str(), major.clone()),
+            Some(name) => (name.as_str(), values(name)),
+            None => ("0", vec![0; major.len()]),
+        };
+        trace!(";WriterTree.make_index.len:{:?}", major.len());
+        Some(TreeIndex::new(major_name, minor_name, &major, &minor))
+    }
+
+    /// Set when the baskets of all branches are written to the file, ending a cluster of
+    /// entries, as ROOT `TTree::SetAutoFlush`: every `auto_flush` entries when it is positive, or
+    /// when the entries since the last cluster take `-auto_flush` bytes before compression when
+    /// it is negative. The number of entries of the first cluster is then used for the next
+    /// ones. With 0, baskets are only written when they are full and clusters are not aligned.
+    ///
+    /// The default is -30000000, as ROOT. Clusters are read with
+    /// [ReaderTree::clusters](crate::ReaderTree::clusters).
+    ///
+    /**
+    ```
+    use oxyroot::{RootFile, WriterTree};
+    let s = "/tmp/auto_flush.root";
+    {
+        let mut file = RootFile::create(s).expect("Can not create file");
+        let mut tree = WriterTree::new("mytree");
+        tree.set_auto_flush(1000);
+        tree.new_branch("it", 0..2500);
+        tree.write(&mut file).expect("Can not write tree");
+        file.close().expect("Can not close file");
+    }
+
+    let tree = RootFile::open(s).unwrap().get_tree("mytree").unwrap();
+    let clusters = tree.clusters().collect::<Vec<_>>();
+    assert_eq!(clusters, [0..1000, 1000..2000, 2000..2500]);
+    ```
+     */
+    pub fn set_auto_flush(&mut self, auto_flush: i64) {
+        self.tree.auto_flush = auto_flush;
+    }
+
     pub(crate) fn add_streamer(&mut self, si: StreamerInfo) {
         let sis = self.tree.sinfos.as_mut().unwrap();
         sis.push(si);
@@ -112,7 +255,11 @@ impl WriterTree {
     /// registered with [add_callback](#method.add_callback) are called for each new entry:
     /// - Before writing branches with the argument [StateCallBack::Before](enum.StateCallBack.html)
     /// - Before writing each branch with the argument [StateCallBack::Branch](enum.StateCallBack.html)
-    pub fn write(&mut self, file: &mut RootFile) -> crate::riofs::Result<()> {
+    ///
+    /// To write the tree in a subdirectory, provide the [DirectoryWriter](crate::DirectoryWriter)
+    /// returned by [RootFile::mkdir](crate::RootFile::mkdir) instead of the file.
+    pub fn write<D: WriteDestination>(&mut self, dir: &mut D) -> crate::riofs::Result<()> {
+        let file = dir.file_mut();
         let mut branchs_done = self
             .tree
             .branches
@@ -120,6 +267,15 @@ impl WriterTree {
             .map(|_b| false)
             .collect::<Vec<_>>();
         let mut branches = std::mem::take(&mut self.tree.branches);
+        let compression = match self.compression {
+            Some(settings) => settings.to_root(),
+            None => file.compression(),
+        };
+        for b in branches.iter_mut() {
+            b.inherit_compression(compression);
+        }
+        // bytes written since the last cluster
+        let mut cluster_bytes = 0;
         loop {
             let mut tot = 0;
             let zip = 0;
@@ -144,6 +300,18 @@ impl WriterTree {
                 break;
             }
             self.tree.entries += 1;
+
+            cluster_bytes += tot as i64;
+            if self.auto_flush_due(cluster_bytes) {
+                trace!(";WriterTree.write.cluster:{:?}", self.tree.entries);
+                for b in branches.iter_mut() {
+                    b.flush_cluster(file)?;
+                }
+                if self.tree.auto_flush < 0 {
+                    self.tree.auto_flush = self.tree.entries;
+                }
+                cluster_bytes = 0;
+            }
         }
         self.tree.branches = branches;
 
@@ -152,6 +320,34 @@ impl WriterTree {
         self.close(file)
     }
 
+    /// A cluster ends after the entry just written.
+    fn auto_flush_due(&self, cluster_bytes: i64) -> bool {
+        match self.tree.auto_flush {
+            0 => false,
+            n if n > 0 => self.tree.entries % n == 0,
+            n => cluster_bytes >= -n,
+        }
+    }
+
+    /// Record the end of the last cluster, as ROOT `TTree::MarkEventCluster`: the clusters of the
+    /// range ending at the last entry have `auto_flush` entries, or all the entries when no
+    /// cluster was ended.
+    fn mark_cluster(&mut self) {
+        let entries = self.tree.entries;
+        let clusters = &mut self.tree.clusters;
+        if entries == 0 || clusters.ranges.last() == Some(&(entries - 1)) {
+            return;
+        }
+        let size = match (self.tree.auto_flush, clusters.ranges.last()) {
+            (n, _) if n > 0 => n,
+            (_, None) => entries,
+            (_, Some(last)) => entries - 1 - last,
+        };
+        trace!(";WriterTree.mark_cluster.size:{:?}", size);
+        clusters.ranges.push(entries - 1);
+        clusters.sizes.push(size);
+    }
+
     fn flush(&mut self, file: &mut RootFile) -> crate::riofs::Result<()> {
         trace!(";WriterTree.flush:{:?}", true);
         for b in self.tree.branches.iter_mut() {
@@ -164,6 +360,8 @@ impl WriterTree {
     fn close(&mut self, file: &mut RootFile) -> crate::riofs::Result<()> {
         trace!(";WriterTree.close:{:?}", true);
         self.flush(file)?;
+        self.mark_cluster();
+        self.tree.index = self.make_index();
 
         // let t: ReaderTree = self.into();
 
@@ -266,10 +464,16 @@ impl Marshaler for WriterTree {
             w.write_object(&leaves)?;
         }
         {
+            // aliases
             w.write_object_nil()?;
-            w.write_object_nil()?;
-            w.write_object_nil()?;
-            w.write_object_nil()?;
+            // index values and index, empty TArrayD and TArrayI
+            w.write_i32(0)?;
+            w.write_i32(0)?;
+            match &self.tree.index {
+                Some(index) => w.write_object_any(index, std::ptr::addr_of!(*index) as usize)?,
+                None => w.write_object_nil()?,
+            };
+            // friends, user info and branch ref
             w.write_object_nil()?;
             w.write_object_nil()?;
             w.write_object_nil()?;
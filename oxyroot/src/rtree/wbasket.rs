@@ -60,7 +60,11 @@ impl WBasket {
         Ok(())
     }
 
-    pub(crate) fn write_to_file(&mut self, file: &mut RootFile) -> Result<BasketBytesWritten> {
+    pub(crate) fn write_to_file(
+        &mut self,
+        file: &mut RootFile,
+        compression: i32,
+    ) -> Result<BasketBytesWritten> {
         trace!(";WBasket.write_to_file.call:{:?}", true);
         trace!(
             ";WBasket.write_to_file.basket.key.rvers:{:?}",
@@ -110,6 +114,7 @@ impl WBasket {
             self.basket.class().to_string(),
             key.cycle() as i16,
             self.wbuf.p().clone(),
+            compression,
             file,
         )?;
 
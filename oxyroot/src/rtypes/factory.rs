@@ -138,6 +138,15 @@ impl<'a> Factory<'a> {
             .ok_or_else(|| Error::ClassNameNotRegisteredInFactory(s.into()))
     }
 
+    /// ROOT class under which `T` is registered, `None` when `T` is not in the factory.
+    pub(crate) fn class_of<T: FactoryItemRead>(&self) -> Option<&'a str> {
+        self.map
+            .iter()
+            .filter(|(_, f)| f().is::<T>())
+            .map(|(s, _)| *s)
+            .min()
+    }
+
     #[allow(dead_code)] // used in tests
     pub fn get_as_box(&self, s: &'a str) -> Option<Box<dyn FactoryItemRead>> {
         let s = self.get(s);
@@ -200,10 +209,13 @@ lazy_static! {
         crate::rdict::streamers::streamer_types::StreamerSTL::register(&mut f);
         crate::rdict::streamers::streamer_types::StreamerSTLstring::register(&mut f);
         crate::rbase::TObjString::register(&mut f);
+        crate::rbase::Named::register(&mut f);
         crate::rbase::AttLine::register(&mut f);
         crate::rbase::AttFill::register(&mut f);
         crate::rbase::AttMarker::register(&mut f);
         crate::rtree::tree::ReaderTree::register(&mut f);
+        crate::rtree::tree::FriendElement::register(&mut f);
+        crate::rtree::tree::TreeIndex::register(&mut f);
         crate::rtree::branch::TBranch::register(&mut f);
         crate::rtree::branch::TBranchElement::register(&mut f);
         crate::rtree::leaf::TLeaf::register(&mut f);
@@ -217,6 +229,7 @@ lazy_static! {
         crate::rtree::leaf::LeafC::register(&mut f);
         crate::rtree::leaf::LeafElement::register(&mut f);
         crate::rtree::basket::Basket::register(&mut f);
+        crate::riofs::dir::TDirectoryFile::register(&mut f);
 
 
         f
@@ -61,7 +61,8 @@ pub const CLONES_ARRAY: i16 = 4;
 pub const LIST: i16 = 5;
 // HashList                 = 0  // ROOT version for THashList
 // HashTable                = 0  // ROOT version for THashTable
-// Map                      = 3  // ROOT version for TMap
+/// ROOT version for TMap
+pub const MAP: i16 = 3;
 /// ROOT version for TObjArray
 pub const OBJ_ARRAY: i16 = 3;
 // RefArray                 = 1  // ROOT version for TRefArray
@@ -188,3 +189,9 @@ pub const LEAF_C: i16 = 1;
 // NtupleD                  = 1  // ROOT version for TNtupleD
 /// ROOT version for TTree
 pub const TREE: i16 = 20;
+/// ROOT version for TFriendElement
+pub const FRIEND_ELEMENT: i16 = 2;
+/// ROOT version for TVirtualIndex
+pub const VIRTUAL_INDEX: i16 = 1;
+/// ROOT version for TTreeIndex
+pub const TREE_INDEX: i16 = 2;
@@ -344,3 +344,73 @@ fn write_vector_f64_100() -> Result<()> {
     write_branch_vector!(f64, 100);
     Ok(())
 }
+
+#[test]
+fn write_compressed_i32_branch() -> Result<()> {
+    const N: i32 = 100_000;
+    let out_dir = format!("{}/compressed", OUT_DIR);
+    fs::create_dir_all(&out_dir)?;
+    let out_file = format!("{}/i32.root", out_dir);
+
+    {
+        let mut f = oxyroot::RootFile::create(&out_file)?;
+        let mut tree = oxyroot::WriterTree::new("mytree");
+        tree.new_branch("i32", (0..N).map(|x| x % 100));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    // 100_000 i32 take 400kB uncompressed
+    assert!(fs::metadata(&out_file)?.len() < 100_000);
+
+    let mut f = oxyroot::RootFile::open(&out_file)?;
+    let tree = f.get_tree("mytree")?;
+    assert_eq!(tree.entries(), N as i64);
+    let b = tree.branch("i32").unwrap().as_iter::<i32>()?;
+    assert!(b.eq((0..N).map(|x| x % 100)));
+    Ok(())
+}
+
+#[test]
+fn write_with_compression_settings() -> Result<()> {
+    use oxyroot::{CompressionAlgorithm, CompressionSettings, CreateOptions};
+    const N: i32 = 100_000;
+    let out_dir = format!("{}/compressed", OUT_DIR);
+    fs::create_dir_all(&out_dir)?;
+    let out_file = format!("{}/settings.root", out_dir);
+
+    {
+        let options = CreateOptions::default()
+            .with_compression(CompressionSettings::new(CompressionAlgorithm::Zstd, 5));
+        let mut f = oxyroot::RootFile::create_with_options(&out_file, options)?;
+        let mut tree = oxyroot::WriterTree::new("mytree");
+        tree.new_branch("file", (0..N).map(|x| x % 100));
+        tree.new_branch("hot", (0..N).map(|x| x % 100));
+        tree.new_branch("archive", (0..N).map(|x| x % 100));
+        tree.new_branch("raw", (0..N).map(|x| x % 100));
+        tree.set_branch_compression(
+            "hot",
+            CompressionSettings::new(CompressionAlgorithm::LZ4, 4),
+        )?;
+        tree.set_branch_compression(
+            "archive",
+            CompressionSettings::new(CompressionAlgorithm::Lzma, 9),
+        )?;
+        tree.set_branch_compression("raw", CompressionSettings::uncompressed())?;
+        assert!(tree
+            .set_branch_compression("missing", CompressionSettings::default())
+            .is_err());
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let mut f = oxyroot::RootFile::open(&out_file)?;
+    assert_eq!(f.compression(), 505);
+    let tree = f.get_tree("mytree")?;
+    for (name, compression) in [("file", 505), ("hot", 404), ("archive", 209), ("raw", 0)] {
+        let b = tree.branch(name).unwrap();
+        assert_eq!(b.compression(), compression);
+        assert!(b.as_iter::<i32>()?.eq((0..N).map(|x| x % 100)));
+    }
+    Ok(())
+}
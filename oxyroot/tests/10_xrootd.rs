@@ -0,0 +1,191 @@
+#![cfg(feature = "xrootd")]
+
+use anyhow::Result;
+use oxyroot::{OpenOptions, RootFile};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type Requests = Arc<Mutex<HashMap<u16, usize>>>;
+
+/// Minimal XRootD data server, serving files of the local file system. If `redirect` is set, the
+/// server redirects every open request to this port.
+fn serve(redirect: Option<u16>) -> Result<(u16, Requests)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let requests = Requests::default();
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let counter = counter.clone();
+            std::thread::spawn(move || handle(stream, redirect, counter));
+        }
+    });
+    Ok((port, requests))
+}
+
+fn respond(stream: &mut TcpStream, streamid: [u8; 2], status: u16, data: &[u8]) -> Result<()> {
+    let mut resp = streamid.to_vec();
+    resp.extend_from_slice(&status.to_be_bytes());
+    resp.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    resp.extend_from_slice(data);
+    stream.write_all(&resp)?;
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, redirect: Option<u16>, requests: Requests) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let mut handshake = [0_u8; 20];
+    stream.read_exact(&mut handshake)?;
+    assert_eq!(&handshake[12..], &[0, 0, 0, 4, 0, 0, 0x07, 0xdc]);
+    respond(&mut stream, [0, 0], 0, &[0, 0, 0x05, 0x20, 0, 0, 0, 1])?;
+
+    let mut file = Vec::new();
+    loop {
+        let mut hdr = [0_u8; 24];
+        if stream.read_exact(&mut hdr).is_err() {
+            return Ok(());
+        }
+        let streamid = [hdr[0], hdr[1]];
+        let id = u16::from_be_bytes([hdr[2], hdr[3]]);
+        let params = &hdr[4..20];
+        let dlen = u32::from_be_bytes([hdr[20], hdr[21], hdr[22], hdr[23]]);
+        let mut data = vec![0; dlen as usize];
+        stream.read_exact(&mut data)?;
+        *requests.lock().unwrap().entry(id).or_default() += 1;
+
+        match id {
+            // protocol
+            3006 => respond(&mut stream, streamid, 0, &[0, 0, 0x05, 0x20, 0, 0, 0, 1])?,
+            // login
+            3007 => respond(&mut stream, streamid, 0, &[7; 16])?,
+            // open
+            3010 => {
+                if let Some(port) = redirect {
+                    let mut resp = (port as i32).to_be_bytes().to_vec();
+                    resp.extend_from_slice(b"127.0.0.1?token=abc");
+                    respond(&mut stream, streamid, 4004, &resp)?;
+                    continue;
+                }
+                match std::fs::read(String::from_utf8(data)?) {
+                    Ok(content) => {
+                        file = content;
+                        respond(&mut stream, streamid, 0, &[0, 0, 0, 1])?;
+                    }
+                    Err(_) => {
+                        let mut resp = 3011_i32.to_be_bytes().to_vec();
+                        resp.extend_from_slice(b"no such file\0");
+                        respond(&mut stream, streamid, 4003, &resp)?;
+                    }
+                }
+            }
+            // stat
+            3017 => {
+                assert_eq!(&params[12..16], &[0, 0, 0, 1]);
+                let stat = format!("42 {} 0 0\0", file.len());
+                respond(&mut stream, streamid, 0, stat.as_bytes())?
+            }
+            // read, answered in two parts
+            3013 => {
+                let offset = i64::from_be_bytes(params[4..12].try_into()?) as usize;
+                let len = i32::from_be_bytes(params[12..16].try_into()?) as usize;
+                let data = &file[offset..offset + len];
+                let (first, second) = data.split_at(len / 2);
+                respond(&mut stream, streamid, 4000, first)?;
+                respond(&mut stream, streamid, 0, second)?;
+            }
+            // readv
+            3025 => {
+                let mut resp = Vec::new();
+                for chunk in data.chunks(16) {
+                    let len = i32::from_be_bytes(chunk[4..8].try_into()?) as usize;
+                    let offset = i64::from_be_bytes(chunk[8..16].try_into()?) as usize;
+                    resp.extend_from_slice(chunk);
+                    resp.extend_from_slice(&file[offset..offset + len]);
+                }
+                respond(&mut stream, streamid, 0, &resp)?;
+            }
+            // close
+            3003 => respond(&mut stream, streamid, 0, &[])?,
+            _ => {
+                let mut resp = 3013_i32.to_be_bytes().to_vec();
+                resp.extend_from_slice(b"unsupported request\0");
+                respond(&mut stream, streamid, 4003, &resp)?;
+            }
+        }
+    }
+}
+
+fn abs_path(s: &str) -> Result<String> {
+    Ok(std::fs::canonicalize(s)?.to_str().unwrap().to_string())
+}
+
+#[test]
+fn open_xrootd_samples() -> Result<()> {
+    let (port, requests) = serve(None)?;
+    for s in [
+        "tests_data/from_uproot/sample-6.10.05-uncompressed.root",
+        "tests_data/from_uproot/sample-6.10.05-zlib.root",
+        "tests_data/from_uproot/sample-6.10.05-lzma.root",
+        "tests_data/from_uproot/sample-6.10.05-lz4.root",
+    ] {
+        let url = format!("root://127.0.0.1:{port}/{}", abs_path(s)?);
+        let mut f = RootFile::open(url)?;
+        let tree = f.get_tree("sample")?;
+        let n = tree.branch("n").unwrap().as_iter::<i32>()?;
+        assert!(n.eq((0..30).map(|i| i % 5)));
+        let str = tree.branch("str").unwrap().as_iter::<String>()?;
+        assert!(str.eq((0..30).map(|i| format!("hey-{i}"))));
+    }
+
+    let requests = requests.lock().unwrap();
+    for id in [3006, 3007, 3010, 3017, 3013, 3025] {
+        assert!(requests.get(&id).is_some(), "request {id} not sent");
+    }
+    Ok(())
+}
+
+#[test]
+fn open_xrootd_redirect() -> Result<()> {
+    let (port, _) = serve(None)?;
+    let (redirector, requests) = serve(Some(port))?;
+    let s = "examples/from_uproot/data/HZZ.root";
+
+    let url = format!("root://127.0.0.1:{redirector}/{}", abs_path(s)?);
+    let tree = RootFile::open(url)?.get_tree("events")?;
+    assert_eq!(tree.entries(), 2421);
+    let njet = tree.branch("NJet").unwrap().as_iter::<i32>()?;
+    let local = RootFile::open(s)?.get_tree("events")?;
+    assert!(njet.eq(local.branch("NJet").unwrap().as_iter::<i32>()?));
+
+    assert_eq!(requests.lock().unwrap().get(&3010), Some(&1));
+    Ok(())
+}
+
+#[test]
+fn open_xrootd_errors() -> Result<()> {
+    let (port, _) = serve(None)?;
+    assert!(RootFile::open(format!("root://127.0.0.1:{port}//missing.root")).is_err());
+    assert!(RootFile::open("root://127.0.0.1:notaport//missing.root").is_err());
+    Ok(())
+}
+
+#[test]
+fn open_xrootd_timeout() -> Result<()> {
+    // the server accepts the connection but never answers the handshake
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    std::thread::spawn(move || {
+        let streams = listener.incoming().flatten().collect::<Vec<_>>();
+        drop(streams);
+    });
+
+    let url = format!("root://127.0.0.1:{port}//file.root");
+    let options = OpenOptions::default().with_timeout(Duration::from_millis(200));
+    let start = Instant::now();
+    assert!(RootFile::open_with_options(url, options).is_err());
+    assert!(start.elapsed() < Duration::from_secs(10));
+    Ok(())
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "parallel")]
+
+use anyhow::Result;
+use oxyroot::{CompressionAlgorithm, CompressionSettings, ReaderAt, RootFile, WriterTree};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::Duration;
+
+#[test]
+fn par_iter_samples() -> Result<()> {
+    for s in [
+        "tests_data/from_uproot/sample-6.10.05-uncompressed.root",
+        "tests_data/from_uproot/sample-6.10.05-zlib.root",
+        "tests_data/from_uproot/sample-6.10.05-lzma.root",
+        "tests_data/from_uproot/sample-6.10.05-lz4.root",
+    ] {
+        let mut f = RootFile::open(s)?;
+        let tree = f.get_tree("sample")?;
+        let n = tree.branch("n").unwrap().as_par_iter::<i32>()?;
+        assert!(n.eq((0..30).map(|i| i % 5)));
+        let str = tree.branch("str").unwrap().as_par_iter::<String>()?;
+        assert!(str.eq((0..30).map(|i| format!("hey-{i}"))));
+        assert!(tree.branch("n").unwrap().as_par_iter::<f64>().is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn par_iter_many_baskets() -> Result<()> {
+    const N: i64 = 200_000;
+    let out_dir = "/tmp/rust/parallel";
+    std::fs::create_dir_all(out_dir)?;
+
+    for algorithm in [
+        CompressionAlgorithm::Zlib,
+        CompressionAlgorithm::Lzma,
+        CompressionAlgorithm::LZ4,
+        CompressionAlgorithm::Zstd,
+    ] {
+        let out_file = format!("{out_dir}/{algorithm:?}.root");
+        {
+            let mut f = RootFile::create(&out_file)?;
+            let mut tree = WriterTree::new("mytree");
+            tree.set_compression(CompressionSettings::new(algorithm, 1));
+            tree.new_branch("i", 0..N);
+            tree.new_branch("s", (0..N).map(|i| format!("evt-{i}")));
+            tree.write(&mut f)?;
+            f.close()?;
+        }
+
+        let mut f = RootFile::open(&out_file)?;
+        let tree = f.get_tree("mytree")?;
+        assert!(tree.branch("i").unwrap().as_par_iter::<i64>()?.eq(0..N));
+
+        let s = tree.branch("s").unwrap();
+        assert!(s.as_par_iter::<String>()?.eq(s.as_iter::<String>()?));
+    }
+    Ok(())
+}
+
+/// Record the threads reading the file, reads are slow as for remote files.
+struct ThreadsReader {
+    data: Vec<u8>,
+    threads: Arc<Mutex<HashSet<ThreadId>>>,
+}
+
+impl ReaderAt for ThreadsReader {
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        self.threads
+            .lock()
+            .unwrap()
+            .insert(std::thread::current().id());
+        std::thread::sleep(Duration::from_millis(2));
+        let start = start as usize;
+        buf.copy_from_slice(&self.data[start..start + buf.len()]);
+        Ok(())
+    }
+}
+
+#[test]
+fn par_iter_reads_baskets_concurrently() -> Result<()> {
+    const N: i64 = 200_000;
+    let out_dir = "/tmp/rust/parallel";
+    std::fs::create_dir_all(out_dir)?;
+    let out_file = format!("{out_dir}/concurrent_reads.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.new_branch("i", 0..N);
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+    let reader = ThreadsReader {
+        data: std::fs::read(&out_file)?,
+        threads: threads.clone(),
+    };
+    let mut f = RootFile::from_reader_at(&out_file, reader)?;
+    let tree = f.get_tree("mytree")?;
+    let i = tree.branch("i").unwrap();
+
+    threads.lock().unwrap().clear();
+    assert!(i.as_iter::<i64>()?.eq(0..N));
+    assert_eq!(threads.lock().unwrap().len(), 1);
+
+    threads.lock().unwrap().clear();
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build()?;
+    pool.install(|| -> Result<()> {
+        assert!(i.as_par_iter::<i64>()?.eq(0..N));
+        Ok(())
+    })?;
+    let n_threads = threads.lock().unwrap().len();
+    assert!(n_threads > 1, "baskets read by {n_threads} thread(s)");
+    Ok(())
+}
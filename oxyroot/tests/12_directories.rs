@@ -0,0 +1,132 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriteDestination};
+
+const SAMPLE: &str = "tests_data/from_uproot/sample-6.10.05-zlib.root";
+
+#[test]
+fn top_directory() -> Result<()> {
+    let mut f = RootFile::open(SAMPLE)?;
+    let dir = f.get_directory("")?;
+    assert_eq!(dir.path(), "");
+    assert_eq!(dir.keys_name().collect::<Vec<_>>(), vec!["sample"]);
+    assert_eq!(f.get_directory("/")?.keys().len(), 1);
+
+    let tree = dir.get_tree("sample")?;
+    assert_eq!(tree.entries(), 30);
+    Ok(())
+}
+
+#[test]
+fn tree_with_path() -> Result<()> {
+    let mut f = RootFile::open(SAMPLE)?;
+    for path in ["sample", "/sample", "//sample"] {
+        let tree = f.get_tree(path)?;
+        let n = tree.branch("n").unwrap().as_iter::<i32>()?;
+        assert!(n.eq((0..30).map(|i| i % 5)));
+    }
+    Ok(())
+}
+
+#[test]
+fn missing_directory() -> Result<()> {
+    let mut f = RootFile::open(SAMPLE)?;
+    assert!(f.get_directory("missing").is_err());
+    assert!(f.get_tree("missing/sample").is_err());
+    // a tree is not a directory
+    assert!(f.get_directory("sample").is_err());
+    assert!(f.get_tree("sample/n").is_err());
+    Ok(())
+}
+
+fn write_events(f: &mut impl WriteDestination, n: i32) -> Result<()> {
+    let mut tree = oxyroot::WriterTree::new("events");
+    tree.new_branch("n", 0..n);
+    tree.write(f)?;
+    Ok(())
+}
+
+#[test]
+fn write_subdirectories() -> Result<()> {
+    let out_dir = "/tmp/rust/directories";
+    std::fs::create_dir_all(out_dir)?;
+    let out_file = format!("{out_dir}/subdirectories.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, 5)?;
+        for (name, n) in [("signal", 10), ("background", 20)] {
+            let mut dir = f.mkdir(name)?;
+            write_events(&mut dir, n)?;
+        }
+        {
+            let mut dir = f.mkdir_all("a/b")?;
+            assert_eq!(dir.path(), "a/b");
+            let mut sub = dir.mkdir("c")?;
+            assert_eq!(sub.path(), "a/b/c");
+            write_events(&mut sub, 30)?;
+        }
+        assert!(f.mkdir("signal").is_err());
+        assert!(f.mkdir("missing/sub").is_err());
+        assert!(f.mkdir("events").is_err());
+        assert_eq!(f.mkdir_all("a/b")?.path(), "a/b");
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(
+        f.keys_name().collect::<Vec<_>>(),
+        vec!["events", "signal", "background", "a"]
+    );
+    for (path, n) in [
+        ("events", 5),
+        ("signal/events", 10),
+        ("background/events", 20),
+        ("a/b/c/events", 30),
+    ] {
+        let tree = f.get_tree(path)?;
+        assert_eq!(tree.entries(), n as i64);
+        assert!(tree.branch("n").unwrap().as_iter::<i32>()?.eq(0..n));
+    }
+
+    let dir = f.get_directory("a/b")?;
+    assert_eq!(dir.name(), "b");
+    assert_eq!(dir.keys_name().collect::<Vec<_>>(), vec!["c"]);
+    let sub = dir.get_directory("c")?;
+    assert_eq!(sub.path(), "a/b/c");
+    assert_eq!(sub.get_tree("events")?.entries(), 30);
+    Ok(())
+}
+
+#[test]
+fn read_cycles() -> Result<()> {
+    let out_dir = "/tmp/rust/directories";
+    std::fs::create_dir_all(out_dir)?;
+    let out_file = format!("{out_dir}/cycles.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, 5)?;
+        write_events(&mut f, 10)?;
+        let mut dir = f.mkdir("dir")?;
+        for n in [1, 2, 3] {
+            write_events(&mut dir, n)?;
+        }
+        drop(dir);
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(f.cycles("events")?, vec![1, 2]);
+    assert_eq!(f.cycles("dir/events")?, vec![1, 2, 3]);
+    assert!(f.cycles("missing")?.is_empty());
+
+    assert_eq!(f.get_tree("events")?.entries(), 10);
+    assert_eq!(f.get_tree("events;2")?.entries(), 10);
+    let tree = f.get_tree("events;1")?;
+    assert!(tree.branch("n").unwrap().as_iter::<i32>()?.eq(0..5));
+    assert_eq!(f.get_tree("dir/events;2")?.entries(), 2);
+    assert_eq!(f.get_tree("dir/events")?.entries(), 3);
+
+    assert!(f.get_tree("events;3").is_err());
+    assert!(f.get_tree("events;last").is_err());
+    assert!(f.get_tree("events;1;2").is_err());
+    Ok(())
+}
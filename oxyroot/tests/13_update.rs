@@ -0,0 +1,120 @@
+use anyhow::Result;
+use oxyroot::rbase::TObjString;
+use oxyroot::{RootFile, WriteDestination, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/update";
+
+fn write_events(f: &mut impl WriteDestination, name: &str, n: i32) -> Result<()> {
+    let mut tree = WriterTree::new(name);
+    tree.new_branch("n", 0..n);
+    tree.new_branch("s", (0..n).map(|i| format!("evt-{i}")));
+    tree.write(f)?;
+    Ok(())
+}
+
+fn check_events(f: &mut RootFile, path: &str, n: i32) -> Result<()> {
+    let tree = f.get_tree(path)?;
+    assert_eq!(tree.entries(), n as i64);
+    assert!(tree.branch("n").unwrap().as_iter::<i32>()?.eq(0..n));
+    let s = tree.branch("s").unwrap().as_iter::<String>()?;
+    assert!(s.eq((0..n).map(|i| format!("evt-{i}"))));
+    Ok(())
+}
+
+#[test]
+fn update_file_written_by_root() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/sample.root");
+    std::fs::copy("tests_data/from_uproot/sample-6.10.05-zlib.root", &out_file)?;
+
+    {
+        let mut f = RootFile::open_update(&out_file)?;
+        // existing objects can still be read
+        assert_eq!(f.get_tree("sample")?.entries(), 30);
+        write_events(&mut f, "events", 100)?;
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(f.keys_name().collect::<Vec<_>>(), vec!["sample", "events"]);
+    let tree = f.get_tree("sample")?;
+    let n = tree.branch("n").unwrap().as_iter::<i32>()?;
+    assert!(n.eq((0..30).map(|i| i % 5)));
+    let str = tree.branch("str").unwrap().as_iter::<String>()?;
+    assert!(str.eq((0..30).map(|i| format!("hey-{i}"))));
+    check_events(&mut f, "events", 100)?;
+    Ok(())
+}
+
+#[test]
+fn update_file_several_times() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/several_times.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "events", 10)?;
+        let mut dir = f.mkdir("dir")?;
+        write_events(&mut dir, "events", 20)?;
+        drop(dir);
+        f.close()?;
+    }
+
+    for i in 1..=3 {
+        let mut f = RootFile::open_update(&out_file)?;
+        // a new cycle in an existing directory, and a new directory
+        let mut dir = f.mkdir_all("dir")?;
+        write_events(&mut dir, "events", 20 + i)?;
+        let mut sub = dir.mkdir(&format!("sub{i}"))?;
+        write_events(&mut sub, "events", i)?;
+        drop(sub);
+        drop(dir);
+        assert!(f.mkdir("dir").is_err());
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    check_events(&mut f, "events", 10)?;
+    assert_eq!(f.cycles("dir/events")?, vec![1, 2, 3, 4]);
+    check_events(&mut f, "dir/events;1", 20)?;
+    check_events(&mut f, "dir/events", 23)?;
+    for i in 1..=3 {
+        check_events(&mut f, &format!("dir/sub{i}/events"), i)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn update_read_back_before_close() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/read_back.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "events", 10)?;
+        f.close()?;
+    }
+
+    let mut f = RootFile::open_update(&out_file)?;
+    f.put("note", &TObjString::new("added in update mode"))?;
+    assert_eq!(
+        f.get::<TObjString>("note")?.to_string(),
+        "added in update mode"
+    );
+    write_events(&mut f, "more", 1000)?;
+    check_events(&mut f, "more", 1000)?;
+    check_events(&mut f, "events", 10)?;
+    f.close()?;
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(
+        f.get::<TObjString>("note")?.to_string(),
+        "added in update mode"
+    );
+    check_events(&mut f, "more", 1000)?;
+    Ok(())
+}
+
+#[test]
+fn update_missing_file() -> Result<()> {
+    assert!(RootFile::open_update(format!("{OUT_DIR}/missing.root")).is_err());
+    Ok(())
+}
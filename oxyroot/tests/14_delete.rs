@@ -0,0 +1,146 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriteDestination, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/delete";
+
+fn write_events(f: &mut impl WriteDestination, name: &str, n: i32) -> Result<()> {
+    let mut tree = WriterTree::new(name);
+    tree.new_branch("n", 0..n);
+    tree.new_branch("s", (0..n).map(|i| format!("evt-{i}")));
+    tree.write(f)?;
+    Ok(())
+}
+
+fn check_events(f: &mut RootFile, path: &str, n: i32) -> Result<()> {
+    let tree = f.get_tree(path)?;
+    assert_eq!(tree.entries(), n as i64);
+    assert!(tree.branch("n").unwrap().as_iter::<i32>()?.eq(0..n));
+    let s = tree.branch("s").unwrap().as_iter::<String>()?;
+    assert!(s.eq((0..n).map(|i| format!("evt-{i}"))));
+    Ok(())
+}
+
+#[test]
+fn delete_and_reuse_space() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/reuse.root");
+    let size = {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "a", 10_000)?;
+        write_events(&mut f, "b", 10_000)?;
+        f.delete("a")?;
+        // written in the space of a
+        write_events(&mut f, "c", 10_000)?;
+        f.close()?;
+        std::fs::metadata(&out_file)?.len()
+    };
+
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "b", 10_000)?;
+        write_events(&mut f, "c", 10_000)?;
+        f.close()?;
+        assert!(size < std::fs::metadata(&out_file)?.len() + 1000);
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(f.keys_name().collect::<Vec<_>>(), vec!["b", "c"]);
+    assert!(f.get_tree("a").is_err());
+    check_events(&mut f, "b", 10_000)?;
+    check_events(&mut f, "c", 10_000)?;
+    Ok(())
+}
+
+#[test]
+fn replace_tree_in_update_mode() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/replace.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "events", 1000)?;
+        let mut dir = f.mkdir("dir")?;
+        write_events(&mut dir, "events", 1000)?;
+        drop(dir);
+        f.close()?;
+    }
+
+    let mut sizes = Vec::new();
+    for _ in 0..5 {
+        let mut f = RootFile::open_update(&out_file)?;
+        f.delete("events")?;
+        write_events(&mut f, "events", 1000)?;
+        f.delete("dir/events")?;
+        let mut dir = f.mkdir_all("dir")?;
+        write_events(&mut dir, "events", 1000)?;
+        drop(dir);
+        f.close()?;
+        sizes.push(std::fs::metadata(&out_file)?.len());
+    }
+    // the file stops growing once the space freed by the first replacements is reused
+    assert!(sizes[2..].iter().all(|s| *s == sizes[2]), "{:?}", sizes);
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(f.cycles("events")?, vec![1]);
+    check_events(&mut f, "events", 1000)?;
+    check_events(&mut f, "dir/events", 1000)?;
+    Ok(())
+}
+
+#[test]
+fn delete_directory() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/directory.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "events", 10)?;
+        let mut dir = f.mkdir_all("dir/sub")?;
+        write_events(&mut dir, "events", 10)?;
+        drop(dir);
+        f.close()?;
+    }
+    {
+        let mut f = RootFile::open_update(&out_file)?;
+        assert!(f.delete("dir/missing").is_err());
+        assert!(f.delete("events/n").is_err());
+        f.delete("dir")?;
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(f.keys_name().collect::<Vec<_>>(), vec!["events"]);
+    check_events(&mut f, "events", 10)?;
+    assert!(f.delete("events").is_err());
+    Ok(())
+}
+
+#[test]
+fn delete_directory_created_in_session() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/directory_in_session.root");
+    let size = {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "b", 10_000)?;
+        let mut dir = f.mkdir("dir")?;
+        write_events(&mut dir, "a", 10_000)?;
+        drop(dir);
+        f.delete("dir")?;
+        // written in the space of dir/a
+        write_events(&mut f, "c", 10_000)?;
+        f.close()?;
+        std::fs::metadata(&out_file)?.len()
+    };
+
+    {
+        let mut f = RootFile::create(&out_file)?;
+        write_events(&mut f, "b", 10_000)?;
+        write_events(&mut f, "c", 10_000)?;
+        f.close()?;
+        assert!(size < std::fs::metadata(&out_file)?.len() + 1000);
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    assert_eq!(f.keys_name().collect::<Vec<_>>(), vec!["b", "c"]);
+    check_events(&mut f, "b", 10_000)?;
+    check_events(&mut f, "c", 10_000)?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use oxyroot::rbase::{Named, TObjString};
+use oxyroot::rcont::list::{ReaderList, WriterList};
+use oxyroot::rcont::tmap::Key;
+use oxyroot::rcont::{TMap, WriterMap};
+use oxyroot::{Named as _, RootFile, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/objects";
+
+#[test]
+fn put_and_get_objects() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/objects.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        f.put("provenance", &TObjString::new("skimmed with cuts v3"))?;
+
+        let cuts = [
+            Named::new("pt", "pt > 20"),
+            Named::new("eta", "|eta| < 2.5"),
+        ];
+        let mut list = WriterList::new();
+        cuts.iter().for_each(|cut| list.add(cut));
+        f.put("cuts", &list)?;
+
+        let (key, value) = (TObjString::new("lumi"), TObjString::new("138"));
+        let mut map = WriterMap::new();
+        map.insert(&key, &value);
+        f.put("meta", &map)?;
+
+        let mut sub = f.mkdir("calib")?;
+        sub.put("", &Named::new("jes", "v7"))?;
+        drop(sub);
+
+        let mut tree = WriterTree::new("t");
+        tree.new_branch("n", 0..10);
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    let note = f.get::<TObjString>("provenance")?;
+    assert_eq!(note.to_string(), "skimmed with cuts v3");
+
+    let cuts = f.get::<ReaderList>("cuts")?;
+    assert_eq!(cuts.len(), 2);
+    assert_eq!(cuts.at::<Named>(0).title(), "pt > 20");
+    assert_eq!(cuts.at::<Named>(1).title(), "|eta| < 2.5");
+
+    let meta = f.get::<TMap>("meta")?;
+    let lumi = meta.get::<TObjString>(&Key::String("lumi".to_string()));
+    assert_eq!(lumi.unwrap().to_string(), "138");
+
+    let jes = f.get::<Named>("calib/jes")?;
+    assert_eq!(jes.title(), "v7");
+    let jes = f.get_directory("calib")?.get::<Named>("jes;1")?;
+    assert_eq!(jes.title(), "v7");
+    Ok(())
+}
+
+#[test]
+fn get_with_wrong_class() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/wrong_class.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        f.put("provenance", &TObjString::new("skimmed with cuts v3"))?;
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    let err = f.get::<Named>("provenance").unwrap_err().to_string();
+    assert!(err.contains("KeyClassMismatch"), "{err}");
+    assert!(err.contains("TObjString"), "{err}");
+    let err = f.get_tree("provenance").err().unwrap().to_string();
+    assert!(err.contains("KeyClassMismatch"), "{err}");
+    assert!(f.get::<TObjString>("missing").is_err());
+    assert!(f.get::<TObjString>("provenance;2").is_err());
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use oxyroot::{Problem, RootFile};
+
+const OUT_DIR: &str = "/tmp/rust/verify";
+
+#[test]
+fn verify_files_from_root() -> Result<()> {
+    for path in [
+        "examples/from_uproot/data/HZZ.root",
+        "tests_data/from_uproot/sample-6.10.05-lz4.root",
+        "tests_data/from_groot/g4-like.root",
+        "tests/stl_containers/stl_containers.root",
+    ] {
+        let report = RootFile::open(path)?.verify()?;
+        assert!(report.is_ok(), "{path}: {report}");
+        assert!(report.n_keys > 0);
+    }
+    Ok(())
+}
+
+#[test]
+fn verify_corrupted_basket() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/HZZ-corrupted.root");
+    let mut data = std::fs::read("examples/from_uproot/data/HZZ.root")?;
+    data[40000..40064].fill(0xff);
+    std::fs::write(&out_file, data)?;
+
+    let report = RootFile::open(&out_file)?.verify()?;
+    assert_eq!(report.n_baskets, 57);
+    match &report.problems[..] {
+        [Problem::BadPayload { path, .. }] => assert_eq!(path, "events;1/Muon_Pz[0]"),
+        _ => panic!("{report}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn verify_truncated_file() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/HZZ-truncated.root");
+    let data = std::fs::read("examples/from_uproot/data/HZZ.root")?;
+    std::fs::write(&out_file, &data[..data.len() / 2])?;
+
+    assert!(RootFile::open(&out_file).is_err());
+    let report = RootFile::verify_path(&out_file)?;
+    assert!(
+        matches!(report.problems[0], Problem::Truncated { .. }),
+        "{report}"
+    );
+    assert!(report.problems.len() > 1, "{report}");
+
+    let report = RootFile::verify_path("examples/from_uproot/data/HZZ.root")?;
+    assert!(report.is_ok(), "{report}");
+    assert!(RootFile::verify_path("Cargo.toml").is_err());
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/recover";
+const N: i32 = 100_000;
+
+/// Write a tree with an integer and a string branch, return the content of the file.
+fn write_tree(path: &str) -> Result<Vec<u8>> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    {
+        let mut f = RootFile::create(path)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.new_branch("n", 0..N);
+        tree.new_branch("s", (0..N).map(|i| format!("evt{i}")));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+    Ok(std::fs::read(path)?)
+}
+
+#[test]
+fn recover_keys_of_file_not_closed() -> Result<()> {
+    let out_file = format!("{OUT_DIR}/not_closed.root");
+    let data = write_tree(&out_file)?;
+    let key = RootFile::open(&out_file)?.keys()[0].clone();
+
+    // keys list, streamer info and free segments are missing
+    let end = (key.seek_key() + key.n_bytes() as i64) as usize;
+    std::fs::write(&out_file, &data[..end])?;
+    assert!(RootFile::open(&out_file).is_err());
+
+    let mut f = RootFile::open_recover(&out_file)?;
+    assert_eq!(f.keys_name().collect::<Vec<_>>(), vec!["mytree"]);
+    let tree = f.get_tree("mytree")?;
+    assert_eq!(tree.entries(), N as i64);
+    let s = tree.branch("s").unwrap().as_iter::<String>()?;
+    assert!(s.enumerate().all(|(i, s)| s == format!("evt{i}")));
+
+    let tree = f.recover_tree("mytree")?;
+    assert_eq!(tree.entries(), N as i64);
+    assert_eq!(
+        tree.branch("n").unwrap().as_iter::<i32>()?.count(),
+        N as usize
+    );
+    Ok(())
+}
+
+#[test]
+fn recover_keys_after_invalid_record() -> Result<()> {
+    let out_file = format!("{OUT_DIR}/invalid_record.root");
+    let mut data = write_tree(&out_file)?;
+    let key = RootFile::open(&out_file)?.keys()[0].clone();
+    let end = (key.seek_key() + key.n_bytes() as i64) as usize;
+    data.truncate(end);
+
+    // fKeylen of the record following the one of the top directory, the first basket, is larger
+    // than the record
+    let begin = 100;
+    let n_bytes = i32::from_be_bytes(data[begin..begin + 4].try_into()?) as usize;
+    let seek = begin + n_bytes;
+    data[seek + 14..seek + 16].copy_from_slice(&i16::MAX.to_be_bytes());
+    std::fs::write(&out_file, &data)?;
+
+    let f = RootFile::open_recover(&out_file)?;
+    assert_eq!(f.keys_name().collect::<Vec<_>>(), vec!["mytree"]);
+    Ok(())
+}
+
+#[test]
+fn recover_baskets_of_tree_not_written() -> Result<()> {
+    let out_file = format!("{OUT_DIR}/tree_not_written.root");
+    let data = write_tree(&out_file)?;
+    let layout = RootFile::open(&out_file)?.get_tree("mytree")?;
+
+    std::fs::write(&out_file, &data[..data.len() / 2])?;
+    assert!(RootFile::open(&out_file).is_err());
+
+    let mut f = RootFile::open_recover(&out_file)?;
+    assert_eq!(f.keys_name().count(), 0);
+    assert!(f.get_tree("mytree").is_err());
+
+    let tree = f.recover_tree_from(layout)?;
+    let entries = tree.entries();
+    assert!(entries > 0 && entries < N as i64, "{entries}");
+    let n = tree
+        .branch("n")
+        .unwrap()
+        .as_iter::<i32>()?
+        .collect::<Vec<_>>();
+    let s = tree
+        .branch("s")
+        .unwrap()
+        .as_iter::<String>()?
+        .collect::<Vec<_>>();
+    assert_eq!(entries, n.len().min(s.len()) as i64);
+    assert!(n.iter().enumerate().all(|(i, &n)| n == i as i32));
+    assert!(s.iter().enumerate().all(|(i, s)| *s == format!("evt{i}")));
+    Ok(())
+}
+
+#[test]
+fn open_recover_sound_file() -> Result<()> {
+    let mut f = RootFile::open_recover("examples/from_uproot/data/HZZ.root")?;
+    let entries = f.get_tree("events")?.entries();
+    assert_eq!(f.recover_tree("events")?.entries(), entries);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/random_access";
+
+#[test]
+fn get_entry_samples() -> Result<()> {
+    for s in [
+        "tests_data/from_uproot/sample-6.10.05-uncompressed.root",
+        "tests_data/from_uproot/sample-6.10.05-zlib.root",
+        "tests_data/from_uproot/sample-6.10.05-lzma.root",
+        "tests_data/from_uproot/sample-6.10.05-lz4.root",
+    ] {
+        let mut f = RootFile::open(s)?;
+        let tree = f.get_tree("sample")?;
+        let n = tree.branch("n").unwrap();
+        let str = tree.branch("str").unwrap();
+        let ai4 = tree.branch("ai4").unwrap();
+        let all = ai4.as_iter::<[i32; 3]>()?.collect::<Vec<_>>();
+        for i in 0..30 {
+            assert_eq!(n.get_entry::<i32>(i)?, i as i32 % 5);
+            assert_eq!(str.get_entry::<String>(i)?, format!("hey-{i}"));
+            assert_eq!(ai4.get_entry::<[i32; 3]>(i)?, all[i as usize]);
+        }
+        assert!(n.get_entry::<i32>(30).is_err());
+        assert!(n.get_entry::<i32>(-1).is_err());
+        assert!(n.get_entry::<f64>(0).is_err());
+
+        let range = str.as_iter_range::<String>(7..23)?;
+        assert!(range.eq((7..23).map(|i| format!("hey-{i}"))));
+        assert!(ai4
+            .as_iter_range::<[i32; 3]>(12..40)?
+            .eq(all[12..].iter().cloned()));
+        assert_eq!(n.as_iter_range::<i32>(30..40)?.count(), 0);
+    }
+    Ok(())
+}
+
+#[test]
+fn get_entry_vector_of_string() -> Result<()> {
+    let s = "examples/from_uproot/data/small-evnt-tree-fullsplit.root";
+    let tree = RootFile::open(s)?.get_tree("tree")?;
+    let b = tree.branch("StlVecStr").unwrap();
+    let all = b.as_iter::<Vec<String>>()?.collect::<Vec<_>>();
+    for (i, v) in all.iter().enumerate() {
+        assert_eq!(b.get_entry::<Vec<String>>(i as i64)?, *v);
+    }
+    assert!(b
+        .as_iter_range::<Vec<String>>(37..63)?
+        .eq(all[37..63].iter().cloned()));
+    Ok(())
+}
+
+#[test]
+fn iter_range_many_baskets() -> Result<()> {
+    const N: i64 = 200_000;
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/many_baskets.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.new_branch("i", 0..N);
+        tree.new_branch("s", (0..N).map(|i| format!("evt-{i}")));
+        tree.new_branch("v", (0..N).map(|i| vec![i as f64; (i % 4) as usize]));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let mut f = RootFile::open(&out_file)?;
+    let tree = f.get_tree("mytree")?;
+    let i = tree.branch("i").unwrap();
+    let s = tree.branch("s").unwrap();
+    let v = tree.branch("v").unwrap();
+    for entry in [0, 1, 8191, 8192, 123_456, N - 1] {
+        assert_eq!(i.get_entry::<i64>(entry)?, entry);
+        assert_eq!(s.get_entry::<String>(entry)?, format!("evt-{entry}"));
+        let expected = vec![entry as f64; (entry % 4) as usize];
+        assert_eq!(v.get_entry::<Vec<f64>>(entry)?, expected);
+    }
+    assert!(i.get_entry::<i64>(N).is_err());
+
+    assert!(i.as_iter_range::<i64>(99_990..100_010)?.eq(99_990..100_010));
+    assert!(i.as_iter_range::<i64>(N - 5..N + 5)?.eq(N - 5..N));
+    let range = s.as_iter_range::<String>(150_000..150_100)?;
+    assert!(range.eq((150_000..150_100).map(|i| format!("evt-{i}"))));
+    Ok(())
+}
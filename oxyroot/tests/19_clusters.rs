@@ -0,0 +1,116 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/clusters";
+
+#[test]
+fn write_clusters_every_n_entries() -> Result<()> {
+    const N: i64 = 10_500;
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/auto_flush_entries.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.set_auto_flush(1000);
+        tree.new_branch("i", 0..N);
+        tree.new_branch("s", (0..N).map(|i| format!("evt-{i}")));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let tree = RootFile::open(&out_file)?.get_tree("mytree")?;
+    let clusters = tree.clusters().collect::<Vec<_>>();
+    let expected = (0..N)
+        .step_by(1000)
+        .map(|start| start..(start + 1000).min(N))
+        .collect::<Vec<_>>();
+    assert_eq!(clusters, expected);
+
+    let mut seen = Vec::new();
+    tree.for_each_cluster(&["i", "s"], |chunk| {
+        let entries = chunk.entries();
+        let i = chunk.as_iter::<i64>("i").unwrap();
+        let s = chunk.as_iter::<String>("s").unwrap();
+        assert!(i.eq(entries.clone()));
+        assert!(s.eq(entries.clone().map(|i| format!("evt-{i}"))));
+        assert!(chunk.as_iter::<i64>("missing").is_err());
+        seen.push(entries);
+    })?;
+    assert_eq!(seen, expected);
+
+    assert!(tree.for_each_cluster(&["missing"], |_| {}).is_err());
+    Ok(())
+}
+
+#[test]
+fn write_clusters_every_n_bytes() -> Result<()> {
+    const N: i64 = 100_000;
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/auto_flush_bytes.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.set_auto_flush(-100_000);
+        tree.new_branch("i", 0..N);
+        tree.new_branch("x", (0..N).map(|i| i as f64));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let tree = RootFile::open(&out_file)?.get_tree("mytree")?;
+    let clusters = tree.clusters().collect::<Vec<_>>();
+    assert!(clusters.len() > 1, "{clusters:?}");
+    let size = clusters[0].end;
+    assert!(clusters
+        .iter()
+        .rev()
+        .skip(1)
+        .all(|c| c.end - c.start == size));
+    assert_eq!(clusters.last().unwrap().end, N);
+
+    let mut sum = 0;
+    tree.for_each_cluster(&["i"], |chunk| {
+        sum += chunk.as_iter::<i64>("i").unwrap().sum::<i64>();
+    })?;
+    assert_eq!(sum, N * (N - 1) / 2);
+    Ok(())
+}
+
+#[test]
+fn clusters_of_small_tree() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/small.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.new_branch("i", 0..42);
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let tree = RootFile::open(&out_file)?.get_tree("mytree")?;
+    assert_eq!(tree.clusters().collect::<Vec<_>>(), vec![0..42]);
+    Ok(())
+}
+
+#[test]
+fn clusters_cover_root_trees() -> Result<()> {
+    for (s, name) in [
+        ("examples/from_uproot/data/HZZ.root", "events"),
+        (
+            "examples/from_uproot/data/small-evnt-tree-fullsplit.root",
+            "tree",
+        ),
+        ("tests_data/from_uproot/sample-6.10.05-zlib.root", "sample"),
+    ] {
+        let tree = RootFile::open(s)?.get_tree(name)?;
+        let mut next = 0;
+        for c in tree.clusters() {
+            assert_eq!(c.start, next, "{s}");
+            assert!(c.end > c.start, "{s}");
+            next = c.end;
+        }
+        assert_eq!(next, tree.entries(), "{s}");
+    }
+    Ok(())
+}
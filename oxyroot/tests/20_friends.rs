@@ -0,0 +1,86 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/friends";
+const N: i32 = 1000;
+
+/// Write a tree `events` with branches `n` and `x`, and a tree `calib` with branches `x` and
+/// `scale` in another file, return the paths of the files.
+fn write_trees(prefix: &str) -> Result<(String, String)> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let main = format!("{OUT_DIR}/{prefix}_main.root");
+    let calib = format!("{OUT_DIR}/{prefix}_calib.root");
+    {
+        let mut f = RootFile::create(&main)?;
+        let mut tree = WriterTree::new("events");
+        tree.new_branch("n", 0..N);
+        tree.new_branch("x", (0..N).map(|i| i as f64));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+    {
+        let mut f = RootFile::create(&calib)?;
+        let mut tree = WriterTree::new("calib");
+        tree.new_branch("x", (0..N).map(|i| -i as f64));
+        tree.new_branch("scale", (0..N).map(|i| format!("s{i}")));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+    Ok((main, calib))
+}
+
+#[test]
+fn friend_from_other_file() -> Result<()> {
+    let (main, calib) = write_trees("other_file")?;
+    let mut tree = RootFile::open(&main)?.get_tree("events")?;
+    assert!(tree.friend_elements().is_empty());
+    tree.add_friend(RootFile::open(&calib)?.get_tree("calib")?, None);
+    assert_eq!(
+        tree.friends().map(|(a, _)| a).collect::<Vec<_>>(),
+        ["calib"]
+    );
+    assert_eq!(tree.friend("calib").unwrap().entries(), N as i64);
+
+    // branches of the tree first
+    let x = tree.branch("x").unwrap().as_iter::<f64>()?;
+    assert!(x.eq((0..N).map(|i| i as f64)));
+    let x = tree.branch("calib.x").unwrap().as_iter::<f64>()?;
+    assert!(x.eq((0..N).map(|i| -i as f64)));
+    let scale = tree.branch("scale").unwrap().as_iter::<String>()?;
+    assert!(scale.eq((0..N).map(|i| format!("s{i}"))));
+    assert!(tree.branch("calib.n").is_none());
+    assert!(tree.branch("other.x").is_none());
+
+    // entries of friends are aligned
+    let n = tree.branch("n").unwrap().as_iter::<i32>()?;
+    let s = tree.branch("calib.scale").unwrap().as_iter::<String>()?;
+    assert!(n.zip(s).all(|(n, s)| s == format!("s{n}")));
+    Ok(())
+}
+
+#[test]
+fn friend_aliases() -> Result<()> {
+    let (main, calib) = write_trees("aliases")?;
+    let mut tree = RootFile::open(&main)?.get_tree("events")?;
+    tree.add_friend(RootFile::open(&calib)?.get_tree("calib")?, Some("c"));
+    tree.add_friend(RootFile::open(&main)?.get_tree("events")?, Some("self"));
+    assert!(tree.branch("calib.x").is_none());
+    assert_eq!(
+        tree.branch("c.x").unwrap().as_iter::<f64>()?.nth(3),
+        Some(-3.0)
+    );
+    assert_eq!(
+        tree.branch("self.x").unwrap().as_iter::<f64>()?.nth(3),
+        Some(3.0)
+    );
+
+    // a friend with the same alias is replaced
+    tree.add_friend(RootFile::open(&main)?.get_tree("events")?, Some("c"));
+    assert_eq!(tree.friends().count(), 2);
+    assert_eq!(
+        tree.branch("c.x").unwrap().as_iter::<f64>()?.nth(3),
+        Some(3.0)
+    );
+    assert!(tree.branch("scale").is_none());
+    Ok(())
+}
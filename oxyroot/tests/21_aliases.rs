@@ -0,0 +1,67 @@
+use anyhow::Result;
+use oxyroot::RootFile;
+
+const HZZ: &str = "examples/from_uproot/data/HZZ.root";
+
+#[test]
+fn aliases_renaming_branches() -> Result<()> {
+    let mut tree = RootFile::open(HZZ)?.get_tree("events")?;
+    assert_eq!(tree.aliases().count(), 0);
+    tree.set_alias("n_jets", "NJet");
+    tree.set_alias("jets", " n_jets ");
+    tree.set_alias("pt", "sqrt(Jet_Px*Jet_Px+Jet_Py*Jet_Py)");
+    assert_eq!(
+        tree.aliases().collect::<Vec<_>>(),
+        [
+            ("n_jets", "NJet"),
+            ("jets", " n_jets "),
+            ("pt", "sqrt(Jet_Px*Jet_Px+Jet_Py*Jet_Py)")
+        ]
+    );
+    assert_eq!(tree.alias("pt"), Some("sqrt(Jet_Px*Jet_Px+Jet_Py*Jet_Py)"));
+    assert_eq!(tree.alias("missing"), None);
+
+    let expected = tree
+        .branch("NJet")
+        .unwrap()
+        .as_iter::<i32>()?
+        .collect::<Vec<_>>();
+    for name in ["n_jets", "jets"] {
+        let n = tree.branch(name).unwrap().as_iter::<i32>()?;
+        assert!(n.eq(expected.iter().cloned()), "{name}");
+    }
+    assert!(tree.branch("pt").is_none());
+
+    // an alias is replaced, branches hide aliases
+    tree.set_alias("n_jets", "NMuon");
+    tree.set_alias("NJet", "NMuon");
+    assert_eq!(tree.aliases().count(), 4);
+    assert_eq!(tree.branch("n_jets").unwrap().name(), "NMuon");
+    assert_eq!(tree.branch("jets").unwrap().name(), "NMuon");
+    assert_eq!(tree.branch("NJet").unwrap().name(), "NJet");
+    Ok(())
+}
+
+#[test]
+fn aliases_cycles() -> Result<()> {
+    let mut tree = RootFile::open(HZZ)?.get_tree("events")?;
+    tree.set_alias("a", "b");
+    tree.set_alias("b", "c");
+    tree.set_alias("c", "a");
+    tree.set_alias("d", "d");
+    for name in ["a", "b", "c", "d"] {
+        assert!(tree.branch(name).is_none(), "{name}");
+    }
+    Ok(())
+}
+
+#[test]
+fn aliases_of_friend_branches() -> Result<()> {
+    let mut tree = RootFile::open(HZZ)?.get_tree("events")?;
+    let mut friend = RootFile::open("tests_data/from_uproot/sample-6.10.05-zlib.root")?;
+    tree.add_friend(friend.get_tree("sample")?, Some("s"));
+    tree.set_alias("label", "s.str");
+    let label = tree.branch("label").unwrap().as_iter::<String>()?;
+    assert!(label.eq((0..30).map(|i| format!("hey-{i}"))));
+    Ok(())
+}
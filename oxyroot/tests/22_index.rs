@@ -0,0 +1,123 @@
+use anyhow::Result;
+use oxyroot::{RootFile, WriterTree};
+
+const OUT_DIR: &str = "/tmp/rust/index";
+const N: i64 = 10_000;
+
+/// Runs decreasing every 100 entries, events in shuffled order within a run.
+fn run_event(i: i64) -> (i64, i64) {
+    (1000 - i / 100, (i * 37) % 100)
+}
+
+#[test]
+fn index_run_event() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/run_event.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("events");
+        tree.new_branch("run", (0..N).map(|i| run_event(i).0 as i32));
+        tree.new_branch("event", (0..N).map(|i| run_event(i).1 as u16));
+        tree.new_branch("x", (0..N).map(|i| i as f64));
+        tree.build_index("run", Some("event"))?;
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let tree = RootFile::open(&out_file)?.get_tree("events")?;
+    let index = tree.index().unwrap();
+    assert_eq!(index.major_name(), "run");
+    assert_eq!(index.minor_name(), "event");
+    assert_eq!(index.len(), N as usize);
+
+    let runs = tree.branch("run").unwrap().as_iter::<i32>()?;
+    let events = tree.branch("event").unwrap().as_iter::<u16>()?;
+    for (i, (run, event)) in runs.zip(events).enumerate() {
+        let entry = tree.entry_with_index(run as i64, event as i64);
+        assert_eq!(entry, Some(i as i64), "{run} {event}");
+    }
+
+    let entry = tree.entry_with_index(990, 42).unwrap();
+    let x = tree.branch("x").unwrap().get_entry::<f64>(entry)?;
+    assert_eq!(run_event(x as i64), (990, 42));
+
+    assert_eq!(tree.entry_with_index(990, 100), None);
+    assert_eq!(tree.entry_with_index(900, 0), None);
+    assert_eq!(tree.entry_with_index(-1, -1), None);
+    Ok(())
+}
+
+#[test]
+fn index_major_only() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let out_file = format!("{OUT_DIR}/major_only.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = WriterTree::new("events");
+        tree.new_branch("id", (0..N).map(|i| (i * 7919) % N));
+        tree.build_index("id", None)?;
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let tree = RootFile::open(&out_file)?.get_tree("events")?;
+    assert_eq!(tree.index().unwrap().minor_name(), "0");
+    let ids = tree.branch("id").unwrap().as_iter::<i64>()?;
+    for (i, id) in ids.enumerate() {
+        assert_eq!(tree.entry_with_index(id, 0), Some(i as i64));
+        assert_eq!(tree.entry_with_index(id, 1), None);
+    }
+    Ok(())
+}
+
+#[test]
+fn index_errors() -> Result<()> {
+    let mut tree = WriterTree::new("events");
+    tree.new_branch("n", 0..10);
+    tree.new_branch("x", (0..10).map(|i| i as f64));
+    assert!(tree.build_index("missing", None).is_err());
+    assert!(tree.build_index("n", Some("missing")).is_err());
+    assert!(tree.build_index("x", None).is_err());
+    assert!(tree.build_index("n", Some("x")).is_err());
+    Ok(())
+}
+
+#[test]
+fn no_index() -> Result<()> {
+    let tree = RootFile::open("examples/from_uproot/data/HZZ.root")?.get_tree("events")?;
+    assert!(tree.index().is_none());
+    assert_eq!(tree.entry_with_index(0, 0), None);
+    Ok(())
+}
+
+/// The file is written by ROOT with `tests_data/gen-root/tree_index.C`, run with
+/// `cargo test --test 22_index -- --ignored` once it is in `tests_data/root`.
+#[test]
+#[ignore]
+fn index_written_by_root() -> Result<()> {
+    let mut f = RootFile::open("tests_data/root/tree_index.root")?;
+
+    let tree = f.get_tree("events")?;
+    let index = tree.index().unwrap();
+    assert_eq!(index.major_name(), "run");
+    assert_eq!(index.minor_name(), "event");
+    assert_eq!(index.len(), N as usize);
+    let runs = tree.branch("run").unwrap().as_iter::<i32>()?;
+    let events = tree.branch("event").unwrap().as_iter::<u16>()?;
+    for (i, (run, event)) in runs.zip(events).enumerate() {
+        assert_eq!(run_event(i as i64), (run as i64, event as i64));
+        let entry = tree.entry_with_index(run as i64, event as i64);
+        assert_eq!(entry, Some(i as i64), "{run} {event}");
+    }
+    assert_eq!(tree.entry_with_index(990, 100), None);
+
+    let tree = f.get_tree("ids")?;
+    let index = tree.index().unwrap();
+    assert_eq!(index.major_name(), "id");
+    assert_eq!(index.minor_name(), "0");
+    let ids = tree.branch("id").unwrap().as_iter::<i64>()?;
+    for (i, id) in ids.enumerate() {
+        assert_eq!(tree.entry_with_index(id, 0), Some(i as i64));
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use oxyroot::{CompressionAlgorithm, CompressionSettings, ReaderAt, RootFile, WriterTree};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const OUT_DIR: &str = "/tmp/rust/basket_errors";
+const N: i64 = 100_000;
+
+/// Write a tree `mytree` with a branch `i` of `N` entries compressed with LZ4, the other records
+/// use the default compression of the file.
+fn write_lz4_tree(out_file: &str) -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let mut f = RootFile::create(out_file)?;
+    let mut tree = WriterTree::new("mytree");
+    tree.set_compression(CompressionSettings::new(CompressionAlgorithm::LZ4, 4));
+    tree.new_branch("i", 0..N);
+    tree.write(&mut f)?;
+    f.close()?;
+    Ok(())
+}
+
+#[test]
+fn lz4_checksum_mismatch() -> Result<()> {
+    let out_file = format!("{OUT_DIR}/lz4_checksum.root");
+    write_lz4_tree(&out_file)?;
+
+    // flip a byte of the compressed data of the first LZ4 block, after its header and checksum
+    let mut data = std::fs::read(&out_file)?;
+    let block = data.windows(2).position(|w| w == b"L4").unwrap();
+    data[block + 9 + 8 + 4] ^= 0xff;
+    std::fs::write(&out_file, data)?;
+
+    let tree = RootFile::open(&out_file)?.get_tree("mytree")?;
+    let i = tree.branch("i").unwrap();
+    let err = i
+        .try_as_iter::<i64>()?
+        .collect::<oxyroot::Result<Vec<_>>>()
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("ChecksumMismatch"), "{err:?}");
+    assert!(i.try_as_iter::<i64>()?.last().unwrap().is_err());
+
+    // other iterators stop at the corrupted basket
+    assert!(i.as_iter::<i64>()?.count() < N as usize);
+    assert!(i.get_entry::<i64>(0).is_err());
+    Ok(())
+}
+
+/// Content of a file which is truncated after the tree is read, as files still being copied.
+struct Truncated {
+    data: Vec<u8>,
+    len: Arc<AtomicUsize>,
+}
+
+impl ReaderAt for Truncated {
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        let len = self.len.load(Ordering::Relaxed);
+        let end = start as usize + buf.len();
+        if end > len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.copy_from_slice(&self.data[start as usize..end]);
+        Ok(())
+    }
+}
+
+#[test]
+fn truncated_baskets() -> Result<()> {
+    let out_file = format!("{OUT_DIR}/truncated.root");
+    write_lz4_tree(&out_file)?;
+    let data = std::fs::read(&out_file)?;
+    let len = Arc::new(AtomicUsize::new(data.len()));
+    let reader = Truncated {
+        len: len.clone(),
+        data,
+    };
+
+    let mut f = RootFile::from_reader_at("truncated", reader)?;
+    let tree = f.get_tree("mytree")?;
+    len.store(len.load(Ordering::Relaxed) / 2, Ordering::Relaxed);
+    let i = tree.branch("i").unwrap();
+
+    let entries = i.try_as_iter::<i64>()?.collect::<Vec<_>>();
+    let (last, entries) = entries.split_last().unwrap();
+    assert!(last.is_err(), "{last:?}");
+    assert!(entries.len() < N as usize);
+    assert!(entries
+        .iter()
+        .enumerate()
+        .all(|(n, i)| *i.as_ref().unwrap() == n as i64));
+
+    assert_eq!(i.as_iter::<i64>()?.count(), entries.len());
+    assert!(i.get_entry::<i64>(N - 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn zeroed_baskets() -> Result<()> {
+    let out_file = format!("{OUT_DIR}/zeroed.root");
+    write_lz4_tree(&out_file)?;
+    let mut data = std::fs::read(&out_file)?;
+    let len = data.len();
+    data[len / 4..len / 2].fill(0);
+
+    let tree = RootFile::from_bytes(data)?.get_tree("mytree")?;
+    let i = tree.branch("i").unwrap();
+    assert!(i.try_as_iter::<i64>()?.any(|i| i.is_err()));
+    assert!(i.as_iter::<i64>()?.count() < N as usize);
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use oxyroot::rbase::TObjString;
+use oxyroot::{
+    CompressionAlgorithm, CompressionSettings, CreateOptions, Named, RootFile, WriterTree,
+};
+
+const OUT_DIR: &str = "/tmp/rust/big_file";
+
+/// kStartBigFile of ROOT, records after it need 64 bits offsets.
+const START_BIG_FILE: i64 = 2_000_000_000;
+
+/// Writes 2.2GB to the disk, run with `cargo test --test 24_big_file -- --ignored`.
+#[test]
+#[ignore]
+fn keys_above_start_big_file() -> Result<()> {
+    std::fs::create_dir_all(OUT_DIR)?;
+    let path = format!("{OUT_DIR}/big.root");
+    let n = 10_000;
+    let padding = "x".repeat(200_000_000);
+    {
+        let options = CreateOptions::default()
+            .with_compression(CompressionSettings::new(CompressionAlgorithm::Zlib, 0));
+        let mut f = RootFile::create_with_options(&path, options)?;
+        // 2.2GB of uncompressed strings, the objects written next are above kStartBigFile
+        for i in 0..11 {
+            f.put(&format!("padding{i}"), &TObjString::new(padding.as_str()))?;
+        }
+        f.put("note", &TObjString::new("above 2GB"))?;
+        let mut tree = WriterTree::new("tree");
+        tree.new_branch("i", 0..n);
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+    drop(padding);
+
+    let mut f = RootFile::open(&path)?;
+    let keys = f.keys();
+    let note = keys.iter().find(|k| k.name() == "note").unwrap();
+    assert!(note.seek_key() > START_BIG_FILE);
+    assert_eq!(f.get::<TObjString>("note")?.to_string(), "above 2GB");
+    let tree = f.get_tree("tree")?;
+    assert!(tree.branch("i").unwrap().as_iter::<i32>()?.eq(0..n));
+    assert_eq!(
+        f.get::<TObjString>("padding10")?.to_string().len(),
+        200_000_000
+    );
+    let report = f.verify()?;
+    assert!(report.is_ok(), "{report}");
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
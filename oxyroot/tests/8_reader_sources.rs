@@ -0,0 +1,133 @@
+use anyhow::Result;
+use oxyroot::{ReaderAt, RootFile};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const FILES: [&str; 4] = [
+    "tests_data/from_uproot/sample-6.10.05-uncompressed.root",
+    "tests_data/from_uproot/sample-6.10.05-zlib.root",
+    "tests_data/from_uproot/sample-6.10.05-lzma.root",
+    "tests_data/from_uproot/sample-6.10.05-lz4.root",
+];
+
+fn check_sample(mut f: RootFile) -> Result<()> {
+    let tree = f.get_tree("sample")?;
+    assert_eq!(tree.entries(), 30);
+    let n = tree.branch("n").unwrap().as_iter::<i32>()?;
+    assert!(n.eq((0..30).map(|i| i % 5)));
+    let str = tree.branch("str").unwrap().as_iter::<String>()?;
+    assert!(str.eq((0..30).map(|i| format!("hey-{i}"))));
+    Ok(())
+}
+
+#[test]
+fn open_from_bytes() -> Result<()> {
+    for file in FILES {
+        check_sample(RootFile::from_bytes(std::fs::read(file)?)?)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn open_from_reader() -> Result<()> {
+    for file in FILES {
+        check_sample(RootFile::from_reader(Cursor::new(std::fs::read(file)?))?)?;
+    }
+    Ok(())
+}
+
+struct SliceReader(Vec<u8>);
+
+impl ReaderAt for SliceReader {
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        let start = start as usize;
+        buf.copy_from_slice(&self.0[start..start + buf.len()]);
+        Ok(())
+    }
+}
+
+#[test]
+fn open_from_reader_at() -> Result<()> {
+    let reader = SliceReader(std::fs::read(FILES[1])?);
+    check_sample(RootFile::from_reader_at(FILES[1], reader)?)?;
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn open_mmap() -> Result<()> {
+    for file in FILES {
+        check_sample(unsafe { RootFile::open_mmap(file)? })?;
+    }
+    Ok(())
+}
+
+#[test]
+fn open_not_a_root_file() -> Result<()> {
+    assert!(RootFile::from_bytes(vec![0_u8; 1024]).is_err());
+    assert!(RootFile::from_bytes(b"root".as_slice()).is_err());
+    Ok(())
+}
+
+/// Count reads, each read being one request for a remote source.
+struct CountingReader {
+    data: Vec<u8>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl ReaderAt for CountingReader {
+    fn read_at(&self, buf: &mut [u8], start: u64) -> std::io::Result<()> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let start = start as usize;
+        buf.copy_from_slice(&self.data[start..start + buf.len()]);
+        Ok(())
+    }
+}
+
+#[test]
+fn coalesce_basket_reads() -> Result<()> {
+    const N: i32 = 200_000;
+    let out_dir = "/tmp/rust/reader_sources";
+    std::fs::create_dir_all(out_dir)?;
+    let out_file = format!("{out_dir}/two_branches.root");
+    {
+        let mut f = RootFile::create(&out_file)?;
+        let mut tree = oxyroot::WriterTree::new("mytree");
+        tree.new_branch("a", 0..N);
+        tree.new_branch("b", (0..N).map(|i| i as f64));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let reader = CountingReader {
+        data: std::fs::read(&out_file)?,
+        calls: calls.clone(),
+    };
+    let mut f = RootFile::from_reader_at(&out_file, reader)?;
+    let tree = f.get_tree("mytree")?;
+
+    // branches read one after the other: one read per basket
+    let start = calls.load(Ordering::Relaxed);
+    assert!(tree.branch("a").unwrap().as_iter::<i32>()?.eq(0..N));
+    assert!(tree
+        .branch("b")
+        .unwrap()
+        .as_iter::<f64>()?
+        .eq((0..N).map(|i| i as f64)));
+    let separate = calls.load(Ordering::Relaxed) - start;
+    assert!(separate > 2);
+
+    // branches read together: their baskets are fetched in the same reads
+    let start = calls.load(Ordering::Relaxed);
+    let a = tree.branch("a").unwrap().as_iter::<i32>()?;
+    let b = tree.branch("b").unwrap().as_iter::<f64>()?;
+    assert!(a.zip(b).eq((0..N).map(|i| (i, i as f64))));
+    let together = calls.load(Ordering::Relaxed) - start;
+    assert!(
+        together < separate,
+        "{together} reads, {separate} without cache"
+    );
+    Ok(())
+}
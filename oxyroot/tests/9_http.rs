@@ -0,0 +1,151 @@
+#![cfg(feature = "http")]
+
+use anyhow::Result;
+use oxyroot::{RootFile, Slice};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Minimal HTTP server serving files of the crate, answering `Range` requests if `ranges` is set.
+fn serve(ranges: bool) -> Result<(String, Arc<AtomicUsize>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = handle(stream, ranges);
+        }
+    });
+    Ok((url, requests))
+}
+
+fn handle(mut stream: TcpStream, ranges: bool) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let path = line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    let mut range = None;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some(r) = header.to_lowercase().strip_prefix("range: bytes=") {
+            let (beg, end) = r.trim().split_once('-').unwrap();
+            range = Some((beg.parse::<usize>()?, end.parse::<usize>()?));
+        }
+    }
+
+    let data = match std::fs::read(path.strip_prefix('/').unwrap_or(&path)) {
+        Ok(data) => data,
+        Err(_) => {
+            stream.write_all(
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )?;
+            return Ok(());
+        }
+    };
+
+    match range.filter(|_| ranges) {
+        Some((beg, end)) => {
+            let end = end.min(data.len() - 1);
+            write!(
+                stream,
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {beg}-{end}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                data.len(),
+                end + 1 - beg
+            )?;
+            stream.write_all(&data[beg..=end])?;
+        }
+        None => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                data.len()
+            )?;
+            stream.write_all(&data)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn open_http_hzz() -> Result<()> {
+    let (url, requests) = serve(true)?;
+    let s = "examples/from_uproot/data/HZZ.root";
+
+    let local = RootFile::open(s)?.get_tree("events")?;
+    let remote = RootFile::open(format!("{url}/{s}"))?.get_tree("events")?;
+    assert_eq!(remote.entries(), 2421);
+
+    let njet = remote.branch("NJet").unwrap().as_iter::<i32>()?;
+    assert!(njet.eq(local.branch("NJet").unwrap().as_iter::<i32>()?));
+
+    let jet_py = remote.branch("Jet_Py").unwrap().as_iter::<Slice<f32>>()?;
+    let local_jet_py = local.branch("Jet_Py").unwrap().as_iter::<Slice<f32>>()?;
+    assert!(jet_py
+        .map(Slice::into_vec)
+        .eq(local_jet_py.map(Slice::into_vec)));
+
+    // the file is smaller than a block: everything comes from the first request
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn open_http_samples() -> Result<()> {
+    let (url, _) = serve(true)?;
+    for s in [
+        "tests_data/from_uproot/sample-6.10.05-uncompressed.root",
+        "tests_data/from_uproot/sample-6.10.05-zlib.root",
+        "tests_data/from_uproot/sample-6.10.05-lzma.root",
+        "tests_data/from_uproot/sample-6.10.05-lz4.root",
+    ] {
+        let tree = RootFile::open(format!("{url}/{s}"))?.get_tree("sample")?;
+        let n = tree.branch("n").unwrap().as_iter::<i32>()?;
+        assert!(n.eq((0..30).map(|i| i % 5)));
+    }
+    Ok(())
+}
+
+#[test]
+fn open_http_multi_blocks() -> Result<()> {
+    use oxyroot::{CompressionSettings, CreateOptions, WriterTree};
+    const N: i32 = 300_000;
+    let dir = "/tmp/rust/http";
+    std::fs::create_dir_all(dir)?;
+    let s = format!("{dir}/uncompressed.root");
+    {
+        let options =
+            CreateOptions::default().with_compression(CompressionSettings::uncompressed());
+        let mut f = RootFile::create_with_options(&s, options)?;
+        let mut tree = WriterTree::new("mytree");
+        tree.new_branch("f64", (0..N).map(|i| i as f64));
+        tree.write(&mut f)?;
+        f.close()?;
+    }
+
+    let (url, requests) = serve(true)?;
+    // absolute path, served as is
+    let tree = RootFile::open(format!("{url}/{s}"))?.get_tree("mytree")?;
+    let f64 = tree.branch("f64").unwrap().as_iter::<f64>()?;
+    assert!(f64.eq((0..N).map(|i| i as f64)));
+    assert!(requests.load(Ordering::SeqCst) > 1);
+    Ok(())
+}
+
+#[test]
+fn open_http_errors() -> Result<()> {
+    let (url, _) = serve(false)?;
+    let s = "examples/from_uproot/data/HZZ.root";
+    assert!(RootFile::open(format!("{url}/{s}")).is_err());
+
+    let (url, _) = serve(true)?;
+    assert!(RootFile::open(format!("{url}/missing.root")).is_err());
+    Ok(())
+}